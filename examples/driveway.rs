@@ -12,8 +12,11 @@ fn main() {
     let p2 = Arc::new(RwLock::new(Point::new(PointState::Left, "P2".to_string())));
     let s = Arc::new(RwLock::new(Signal::new(
         SignalState::default(),
-        SupportedSignalStates::default()
-            .main(&mut vec![MainSignalState::Hp0, MainSignalState::Ks1]),
+        SupportedSignalStates::default().main(&mut vec![
+            MainSignalState::Hp0,
+            MainSignalState::Ks1,
+            MainSignalState::Ks2,
+        ]),
         "S".to_string(),
         None,
     )));
@@ -1,5 +1,66 @@
+use std::sync::{Arc, RwLock};
+
 use crate::{TrackElement, TrackElementError};
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AdditionalSignalZs3Symbol {
+    #[default]
+    OFF = 0xFF,
+    ONE = 1,
+    TWO = 2,
+    THREE = 3,
+    FOUR = 4,
+    FIVE = 5,
+    SIX = 6,
+    SEVEN = 7,
+    EIGHT = 8,
+    NINE = 9,
+    TEN = 10,
+    ELEVEN = 11,
+    TWELVE = 12,
+    THIRTEEN = 13,
+    FOURTEEN = 14,
+    FIFTEEN = 15,
+    SIXTEEN = 16,
+}
+
+impl TryFrom<u8> for AdditionalSignalZs3Symbol {
+    type Error = TrackElementError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0xFF | 0 => Ok(AdditionalSignalZs3Symbol::OFF),
+            1 => Ok(AdditionalSignalZs3Symbol::ONE),
+            2 => Ok(AdditionalSignalZs3Symbol::TWO),
+            3 => Ok(AdditionalSignalZs3Symbol::THREE),
+            4 => Ok(AdditionalSignalZs3Symbol::FOUR),
+            5 => Ok(AdditionalSignalZs3Symbol::FIVE),
+            6 => Ok(AdditionalSignalZs3Symbol::SIX),
+            7 => Ok(AdditionalSignalZs3Symbol::SEVEN),
+            8 => Ok(AdditionalSignalZs3Symbol::EIGHT),
+            9 => Ok(AdditionalSignalZs3Symbol::NINE),
+            10 => Ok(AdditionalSignalZs3Symbol::TEN),
+            11 => Ok(AdditionalSignalZs3Symbol::ELEVEN),
+            12 => Ok(AdditionalSignalZs3Symbol::TWELVE),
+            13 => Ok(AdditionalSignalZs3Symbol::THIRTEEN),
+            14 => Ok(AdditionalSignalZs3Symbol::FOURTEEN),
+            15 => Ok(AdditionalSignalZs3Symbol::FIFTEEN),
+            16 => Ok(AdditionalSignalZs3Symbol::SIXTEEN),
+            _ => Err(TrackElementError::InvalidAdditionalSignalState),
+        }
+    }
+}
+
+impl AdditionalSignalZs3Symbol {
+    pub fn from_speed(speed: u16) -> Option<Self> {
+        // Zs3 shows the speed in steps of 10 km/h, rounded down to stay on the safe side
+        match speed / 10 {
+            0 => None,
+            step => Self::try_from(step.min(16) as u8).ok(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct AdditionalSignalZs3 {
     is_v: bool,
@@ -23,9 +84,27 @@ impl AdditionalSignalZs3 {
         }
     }
 
+    pub fn new_arc(
+        is_v: bool,
+        symbols: Vec<AdditionalSignalZs3Symbol>,
+        state: AdditionalSignalZs3Symbol,
+        id: String,
+    ) -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::new(is_v, symbols, state, id)))
+    }
+
     pub fn is_v(&self) -> bool {
         self.is_v
     }
+
+    pub fn symbols(&self) -> &[AdditionalSignalZs3Symbol] {
+        &self.symbols
+    }
+
+    pub fn supports(&self, symbol: AdditionalSignalZs3Symbol) -> bool {
+        // An indicator can always be switched dark
+        symbol == AdditionalSignalZs3Symbol::OFF || self.symbols.contains(&symbol)
+    }
 }
 
 impl TrackElement for AdditionalSignalZs3 {
@@ -39,9 +118,10 @@ impl TrackElement for AdditionalSignalZs3 {
         self.state
     }
 
-    fn set_state(&mut self, new_state: Self::State) -> Result<(), crate::TrackElementError> {
-        if self.symbols.contains(&new_state) {
+    fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
+        if self.supports(new_state) {
             self.state = new_state;
+            println!("Zs3 indicator {} is now {:?}", self.id, self.state);
             Ok(())
         } else {
             Err(TrackElementError::InvalidAdditionalSignalState)
@@ -0,0 +1,115 @@
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::{
+    coupled_points::CoupledPoints, crossing::Crossing, derailer::Derailer, double_slip::DoubleSlip,
+    level_crossing::LevelCrossing, point::Point, signal::Signal, three_way_point::ThreeWayPoint,
+    vacancy_section::VacancySection, TrackElement, TrackElementError,
+};
+
+pub const DEFAULT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub trait Confirmable: TrackElement {
+    fn confirmation_timeout(&self) -> Duration {
+        DEFAULT_CONFIRMATION_TIMEOUT
+    }
+}
+
+impl Confirmable for Point {
+    fn confirmation_timeout(&self) -> Duration {
+        self.throw_time() + DEFAULT_CONFIRMATION_TIMEOUT
+    }
+}
+
+impl Confirmable for CoupledPoints {
+    fn confirmation_timeout(&self) -> Duration {
+        self.points()
+            .iter()
+            .map(|p| p.read().unwrap().confirmation_timeout())
+            .max()
+            .unwrap_or(DEFAULT_CONFIRMATION_TIMEOUT)
+    }
+}
+
+impl Confirmable for LevelCrossing {
+    fn confirmation_timeout(&self) -> Duration {
+        self.closing_time() + DEFAULT_CONFIRMATION_TIMEOUT
+    }
+}
+
+impl Confirmable for Signal {}
+impl Confirmable for VacancySection {}
+impl Confirmable for DoubleSlip {}
+impl Confirmable for ThreeWayPoint {}
+impl Confirmable for Crossing {}
+impl Confirmable for Derailer {}
+
+pub struct Confirmation {
+    id: String,
+    deadline: Instant,
+    is_confirmed: Box<dyn Fn() -> bool + Send + Sync>,
+}
+
+impl Confirmation {
+    pub fn new<T: Confirmable + Send + Sync + 'static>(
+        element: Arc<RwLock<T>>,
+        target: T::State,
+    ) -> Self {
+        let (id, timeout) = {
+            let element = element.read().unwrap();
+            (element.id().to_string(), element.confirmation_timeout())
+        };
+        Self {
+            id,
+            deadline: Instant::now() + timeout,
+            is_confirmed: Box::new(move || element.read().unwrap().reported_state() == target),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+pub async fn confirm(confirmations: Vec<Confirmation>) -> Result<(), TrackElementError> {
+    // Deadlines are absolute, so waiting one after another still gives every element its own timeout
+    for confirmation in confirmations {
+        let wait = async {
+            while !(confirmation.is_confirmed)() {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        };
+        tokio::time::timeout_at(confirmation.deadline, wait)
+            .await
+            .map_err(|_| TrackElementError::ConfirmationTimeout(confirmation.id.clone()))?;
+    }
+    Ok(())
+}
+
+pub trait AsyncTrackElement {
+    type State;
+
+    fn set_state(
+        &self,
+        new_state: Self::State,
+    ) -> impl Future<Output = Result<(), TrackElementError>> + Send;
+}
+
+impl<T: Confirmable + Send + Sync + 'static> AsyncTrackElement for Arc<RwLock<T>> {
+    type State = T::State;
+
+    fn set_state(
+        &self,
+        new_state: Self::State,
+    ) -> impl Future<Output = Result<(), TrackElementError>> + Send {
+        let element = self.clone();
+        async move {
+            element.write().unwrap().set_state(new_state)?;
+            confirm(vec![Confirmation::new(element, new_state)]).await
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::sync::{Arc, RwLock};
+
+use crate::vacancy_section::{VacancySection, VacancySectionState};
+use crate::{TrackElement, TrackElementError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AxleCounterHeadState {
+    #[default]
+    Operational,
+    Faulty,
+}
+
+#[derive(Debug)]
+pub struct AxleCounterHead {
+    state: AxleCounterHeadState,
+    reported: Option<AxleCounterHeadState>,
+    id: String,
+}
+
+impl AxleCounterHead {
+    pub fn new(id: String) -> Self {
+        Self {
+            state: AxleCounterHeadState::Operational,
+            reported: None,
+            id,
+        }
+    }
+
+    pub fn new_arc(id: String) -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::new(id)))
+    }
+
+    pub fn report_state(&mut self, state: AxleCounterHeadState) {
+        self.reported = Some(state);
+        println!("Axle counter head {} reports {:?}", self.id, state);
+    }
+}
+
+impl TrackElement for AxleCounterHead {
+    type State = AxleCounterHeadState;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> Self::State {
+        self.reported.unwrap_or(self.state)
+    }
+
+    fn commanded_state(&self) -> Self::State {
+        self.state
+    }
+
+    fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
+        self.state = new_state;
+        self.reported = None;
+        println!("Axle counter head {} is now {:?}", self.id, self.state);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxleDirection {
+    In,
+    Out,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxleCounterReset {
+    // Vorbereitende Freimeldung: the section stays occupied until a train has passed through it
+    Preparatory,
+    // Direkte Freimeldung: the section is free immediately, the next passage has to confirm it
+    Direct,
+}
+
+#[derive(Debug)]
+pub struct AxleCounter {
+    id: String,
+    section: Arc<RwLock<VacancySection>>,
+    heads: Vec<Arc<RwLock<AxleCounterHead>>>,
+    axles_in: u32,
+    axles_out: u32,
+    disturbed: bool,
+    reset: Option<AxleCounterReset>,
+}
+
+impl AxleCounter {
+    pub fn new(
+        id: String,
+        section: Arc<RwLock<VacancySection>>,
+        heads: Vec<Arc<RwLock<AxleCounterHead>>>,
+    ) -> Self {
+        Self {
+            id,
+            section,
+            heads,
+            axles_in: 0,
+            axles_out: 0,
+            disturbed: false,
+            reset: None,
+        }
+    }
+
+    pub fn new_arc(
+        id: String,
+        section: Arc<RwLock<VacancySection>>,
+        heads: Vec<Arc<RwLock<AxleCounterHead>>>,
+    ) -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::new(id, section, heads)))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn section(&self) -> &Arc<RwLock<VacancySection>> {
+        &self.section
+    }
+
+    pub fn axles(&self) -> i64 {
+        self.axles_in as i64 - self.axles_out as i64
+    }
+
+    pub fn pending_reset(&self) -> Option<AxleCounterReset> {
+        self.reset
+    }
+
+    pub fn state(&self) -> VacancySectionState {
+        let head_faulty = self
+            .heads
+            .iter()
+            .any(|head| head.read().unwrap().state() == AxleCounterHeadState::Faulty);
+        if self.disturbed || head_faulty {
+            VacancySectionState::Disturbed
+        } else if self.axles() != 0 || self.reset == Some(AxleCounterReset::Preparatory) {
+            VacancySectionState::Occupied
+        } else {
+            VacancySectionState::Free
+        }
+    }
+
+    pub fn count(
+        &mut self,
+        head_id: &str,
+        direction: AxleDirection,
+    ) -> Result<(), TrackElementError> {
+        let head = self
+            .heads
+            .iter()
+            .find(|head| head.read().unwrap().id() == head_id)
+            .ok_or_else(|| TrackElementError::AxleCounterHeadDoesNotExist(head_id.to_string()))?;
+        if head.read().unwrap().state() == AxleCounterHeadState::Faulty {
+            self.disturbed = true;
+        } else {
+            match direction {
+                AxleDirection::In => self.axles_in += 1,
+                AxleDirection::Out => self.axles_out += 1,
+            }
+            if self.axles_out > self.axles_in {
+                println!("Axle counter {} counted out more axles than in", self.id);
+                self.disturbed = true;
+            } else if self.axles() == 0 && self.axles_in > 0 && self.reset.is_some() {
+                println!("Axle counter {} reset confirmed by passage", self.id);
+                self.reset = None;
+            }
+        }
+        self.update()
+    }
+
+    pub fn reset(
+        &mut self,
+        reset: AxleCounterReset,
+        operator: &str,
+    ) -> Result<(), TrackElementError> {
+        println!("Axle counter {} reset ({:?})", self.id, reset);
+        if self.section.read().unwrap().state().requires_reset() {
+            self.section.write().unwrap().reset(operator)?;
+        }
+        self.axles_in = 0;
+        self.axles_out = 0;
+        self.disturbed = false;
+        self.reset = Some(reset);
+        self.update()
+    }
+
+    pub fn update(&mut self) -> Result<(), TrackElementError> {
+        let state = self.state();
+        let mut section = self.section.write().unwrap();
+        if section.state() != state {
+            section.set_state(state)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+
+use serde_json::{json, Value};
+
+use crate::point::PointState;
+use crate::signal::{
+    AdditionalSignalState, AdditionalSignalZs3Symbol, MainSignalState, SignalState,
+};
+use crate::vacancy_section::VacancySectionState;
+use crate::TrackElementError;
+
+pub trait Backend<S>: Debug + Send + Sync {
+    fn command(&self, id: &str, state: S) -> Result<(), TrackElementError>;
+    fn status(&self, id: &str) -> Option<S>;
+}
+
+#[derive(Debug)]
+pub struct SimulationBackend<S> {
+    states: RwLock<BTreeMap<String, S>>,
+}
+
+impl<S: Copy + Debug + Send + Sync + 'static> SimulationBackend<S> {
+    pub fn new() -> Self {
+        Self {
+            states: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn new_arc() -> Arc<dyn Backend<S>> {
+        Arc::new(Self::new())
+    }
+}
+
+impl<S: Copy + Debug + Send + Sync + 'static> Default for SimulationBackend<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Copy + Debug + Send + Sync> Backend<S> for SimulationBackend<S> {
+    fn command(&self, id: &str, state: S) -> Result<(), TrackElementError> {
+        self.states.write().unwrap().insert(id.to_string(), state);
+        Ok(())
+    }
+
+    fn status(&self, id: &str) -> Option<S> {
+        self.states.read().unwrap().get(id).copied()
+    }
+}
+
+#[derive(Debug)]
+pub struct MockBackend<S> {
+    commands: RwLock<Vec<(String, S)>>,
+    statuses: RwLock<BTreeMap<String, S>>,
+    failing: RwLock<bool>,
+}
+
+impl<S: Copy + Debug + Send + Sync> MockBackend<S> {
+    pub fn new() -> Self {
+        Self {
+            commands: RwLock::new(Vec::new()),
+            statuses: RwLock::new(BTreeMap::new()),
+            failing: RwLock::new(false),
+        }
+    }
+
+    pub fn new_arc() -> Arc<Self> {
+        Arc::new(Self::new())
+    }
+
+    pub fn commands(&self) -> Vec<(String, S)> {
+        self.commands.read().unwrap().clone()
+    }
+
+    pub fn set_status(&self, id: &str, state: S) {
+        self.statuses.write().unwrap().insert(id.to_string(), state);
+    }
+
+    pub fn set_failing(&self, failing: bool) {
+        *self.failing.write().unwrap() = failing;
+    }
+}
+
+impl<S: Copy + Debug + Send + Sync> Default for MockBackend<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Copy + Debug + Send + Sync> Backend<S> for MockBackend<S> {
+    fn command(&self, id: &str, state: S) -> Result<(), TrackElementError> {
+        if *self.failing.read().unwrap() {
+            return Err(TrackElementError::BackendFailed(id.to_string()));
+        }
+        self.commands.write().unwrap().push((id.to_string(), state));
+        Ok(())
+    }
+
+    fn status(&self, id: &str) -> Option<S> {
+        self.statuses.read().unwrap().get(id).copied()
+    }
+}
+
+pub trait Transport: Debug + Send + Sync {
+    fn send(&self, message: Value) -> Result<(), TrackElementError>;
+    fn status(&self, id: &str) -> Option<Value>;
+}
+
+pub trait WireState: Sized {
+    fn to_wire(&self) -> Value;
+    fn from_wire(value: &Value) -> Option<Self>;
+}
+
+#[derive(Debug)]
+pub struct RemoteBackend {
+    transport: Arc<dyn Transport>,
+}
+
+impl RemoteBackend {
+    pub fn new(transport: Arc<dyn Transport>) -> Self {
+        Self { transport }
+    }
+
+    pub fn new_arc(transport: Arc<dyn Transport>) -> Arc<Self> {
+        Arc::new(Self::new(transport))
+    }
+}
+
+impl<S: WireState> Backend<S> for RemoteBackend {
+    fn command(&self, id: &str, state: S) -> Result<(), TrackElementError> {
+        self.transport
+            .send(json!({ "id": id, "state": state.to_wire() }))
+    }
+
+    fn status(&self, id: &str) -> Option<S> {
+        self.transport
+            .status(id)
+            .and_then(|value| S::from_wire(&value))
+    }
+}
+
+impl WireState for PointState {
+    fn to_wire(&self) -> Value {
+        json!(format!("{self:?}"))
+    }
+
+    fn from_wire(value: &Value) -> Option<Self> {
+        match value.as_str()? {
+            "Left" => Some(PointState::Left),
+            "Right" => Some(PointState::Right),
+            "Moving" => Some(PointState::Moving),
+            "NoEndPosition" => Some(PointState::NoEndPosition),
+            _ => None,
+        }
+    }
+}
+
+impl WireState for VacancySectionState {
+    fn to_wire(&self) -> Value {
+        json!(format!("{self:?}"))
+    }
+
+    fn from_wire(value: &Value) -> Option<Self> {
+        match value.as_str()? {
+            "Free" => Some(VacancySectionState::Free),
+            "Occupied" => Some(VacancySectionState::Occupied),
+            "CommunicationError" => Some(VacancySectionState::CommunicationError),
+            "Disturbed" => Some(VacancySectionState::Disturbed),
+            _ => None,
+        }
+    }
+}
+
+impl WireState for SignalState {
+    fn to_wire(&self) -> Value {
+        json!({
+            "main": self.main() as u8,
+            "additional": self.additional() as u8,
+            "zs3": self.zs3() as u8,
+            "zs3v": self.zs3v() as u8,
+        })
+    }
+
+    fn from_wire(value: &Value) -> Option<Self> {
+        let code = |key: &str| -> Option<u8> { value.get(key)?.as_u64()?.try_into().ok() };
+        Some(SignalState::new(
+            MainSignalState::try_from(code("main")?).ok()?,
+            AdditionalSignalState::try_from(code("additional")?).ok()?,
+            AdditionalSignalZs3Symbol::try_from(code("zs3")?).ok()?,
+            AdditionalSignalZs3Symbol::try_from(code("zs3v")?).ok()?,
+        ))
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+pub trait Clock: std::fmt::Debug + Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    pub fn new_arc() -> Arc<dyn Clock> {
+        Arc::new(Self)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Debug)]
+pub struct ManualClock {
+    now: RwLock<Instant>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            now: RwLock::new(Instant::now()),
+        }
+    }
+
+    pub fn new_arc() -> Arc<Self> {
+        Arc::new(Self::new())
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.write().unwrap() += duration;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.read().unwrap()
+    }
+}
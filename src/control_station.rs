@@ -20,6 +20,24 @@ impl ControlStation {
             .set_driveway(start_signal_id, end_signal_id)
     }
 
+    pub fn release_driveway(
+        &self,
+        start_signal_id: &str,
+        end_signal_id: &str,
+    ) -> Result<(), TrackElementError> {
+        self.driveway_manager
+            .release_driveway(start_signal_id, end_signal_id)
+    }
+
+    pub fn reset_vacancy_section(
+        &self,
+        section_id: &str,
+        operator: &str,
+    ) -> Result<(), TrackElementError> {
+        self.driveway_manager
+            .reset_vacancy_section(section_id, operator)
+    }
+
     pub fn start(&self) {
         let driveways = self.driveway_manager.get_driveway_ids();
         loop {
@@ -41,6 +59,34 @@ impl ControlStation {
                         println!("Error: Please provide two valid signals.");
                     }
                 }
+                "release" => {
+                    if let (Some(from), Some(to)) = (args.next(), args.next()) {
+                        println!("Releasing driveway from {from} to {to}");
+                        if let Err(e) = self.driveway_manager.release_driveway(from, to) {
+                            println!("An error occurred: {e:?}");
+                        }
+                    } else {
+                        println!("Error: Please provide two valid signals.");
+                    }
+                }
+                "reset" => {
+                    if let (Some(section), Some(operator)) = (args.next(), args.next()) {
+                        println!("Resetting vacancy section {section} on behalf of {operator}");
+                        if let Err(e) = self
+                            .driveway_manager
+                            .reset_vacancy_section(section, operator)
+                        {
+                            println!("An error occurred: {e:?}");
+                        }
+                    } else {
+                        println!("Error: Please provide a vacancy section and an operator.");
+                    }
+                }
+                "status" => {
+                    for (id, status) in self.driveway_manager.driveway_statuses() {
+                        println!("{id}: {status:?}");
+                    }
+                }
                 "help" => {
                     println!(
                         r#"==== HELP ====
@@ -48,6 +94,15 @@ impl ControlStation {
 set [from] [to]
     Sets the driveway between signals [from] and [to]
 
+release [from] [to]
+    Releases the driveway between signals [from] and [to]
+
+reset [section] [operator]
+    Resets a disturbed vacancy section to free, logged with the [operator]
+
+status
+    Shows the lifecycle status of all driveways
+
 quit
     Exits this control station
 
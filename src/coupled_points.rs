@@ -0,0 +1,114 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+
+use crate::point::{Lockable, Point, PointLock, PointState};
+use crate::{TrackElement, TrackElementError};
+
+#[derive(Debug)]
+pub struct CoupledPoints {
+    id: String,
+    points: Vec<Arc<RwLock<Point>>>,
+    locks: BTreeSet<PointLock>,
+}
+
+impl CoupledPoints {
+    pub fn new(id: String, points: Vec<Arc<RwLock<Point>>>) -> Self {
+        Self {
+            id,
+            points,
+            locks: BTreeSet::new(),
+        }
+    }
+
+    pub fn new_arc(id: String, points: Vec<Arc<RwLock<Point>>>) -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::new(id, points)))
+    }
+
+    pub fn points(&self) -> &[Arc<RwLock<Point>>] {
+        self.points.as_ref()
+    }
+}
+
+impl Lockable for CoupledPoints {
+    fn locks(&self) -> &BTreeSet<PointLock> {
+        &self.locks
+    }
+
+    fn locks_mut(&mut self) -> &mut BTreeSet<PointLock> {
+        &mut self.locks
+    }
+
+    fn lock(&mut self, lock: PointLock) {
+        for point in &self.points {
+            point.write().unwrap().lock(lock.clone());
+        }
+        self.locks.insert(lock);
+    }
+
+    fn unlock(&mut self, lock: &PointLock) {
+        for point in &self.points {
+            point.write().unwrap().unlock(lock);
+        }
+        self.locks.remove(lock);
+    }
+}
+
+impl TrackElement for CoupledPoints {
+    type State = PointState;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> Self::State {
+        let states: Vec<_> = self
+            .points
+            .iter()
+            .map(|p| p.read().unwrap().state())
+            .collect();
+        match states.first() {
+            None => PointState::default(),
+            Some(first) if states.iter().all(|s| s == first) => *first,
+            Some(_) if states.contains(&PointState::Moving) => PointState::Moving,
+            Some(_) => PointState::NoEndPosition,
+        }
+    }
+
+    fn commanded_state(&self) -> Self::State {
+        let states: Vec<_> = self
+            .points
+            .iter()
+            .map(|p| p.read().unwrap().commanded_state())
+            .collect();
+        match states.first() {
+            None => PointState::default(),
+            Some(first) if states.iter().all(|s| s == first) => *first,
+            Some(_) => PointState::NoEndPosition,
+        }
+    }
+
+    fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
+        if !new_state.is_end_position() {
+            return Err(TrackElementError::InvalidPointState(new_state));
+        }
+        let current: Vec<_> = self
+            .points
+            .iter()
+            .map(|p| p.read().unwrap().commanded_state())
+            .collect();
+        if self.is_locked() && current.iter().any(|s| *s != new_state) {
+            return Err(TrackElementError::PointLocked(self.id.clone()));
+        }
+        for (i, point) in self.points.iter().enumerate() {
+            if let Err(e) = point.write().unwrap().set_state(new_state) {
+                // Move the members that were already thrown back so the group stays together
+                for (point, state) in self.points.iter().zip(&current).take(i) {
+                    let _ = point.write().unwrap().set_state(*state);
+                }
+                return Err(e);
+            }
+        }
+        println!("Coupled points {} are now {:?}", self.id, new_state);
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+
+use crate::point::{Lockable, PointLock};
+use crate::{TrackElement, TrackElementError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DerailerState {
+    #[default]
+    OnRail,
+    OffRail,
+}
+
+#[derive(Debug)]
+pub struct Derailer {
+    state: DerailerState,
+    reported: Option<DerailerState>,
+    id: String,
+    locks: BTreeSet<PointLock>,
+}
+
+impl Derailer {
+    pub fn new(state: DerailerState, id: String) -> Self {
+        Self {
+            state,
+            reported: None,
+            id,
+            locks: BTreeSet::new(),
+        }
+    }
+
+    pub fn new_arc(state: DerailerState, id: String) -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::new(state, id)))
+    }
+
+    pub fn report_state(&mut self, state: DerailerState) {
+        self.reported = Some(state);
+        println!("Derailer {} reports {:?}", self.id, state);
+    }
+}
+
+impl Lockable for Derailer {
+    fn locks(&self) -> &BTreeSet<PointLock> {
+        &self.locks
+    }
+
+    fn locks_mut(&mut self) -> &mut BTreeSet<PointLock> {
+        &mut self.locks
+    }
+}
+
+impl TrackElement for Derailer {
+    type State = DerailerState;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> Self::State {
+        self.reported.unwrap_or(self.state)
+    }
+
+    fn commanded_state(&self) -> Self::State {
+        self.state
+    }
+
+    fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
+        if self.is_locked() && new_state != self.state {
+            return Err(TrackElementError::PointLocked(self.id.clone()));
+        }
+        self.state = new_state;
+        self.reported = None;
+        println!("Derailer {} is now {:?}", self.id, self.state);
+        Ok(())
+    }
+}
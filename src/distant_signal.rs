@@ -0,0 +1,138 @@
+use std::sync::{Arc, RwLock};
+
+use crate::signal::{
+    AdditionalSignalState, AdditionalSignalZs3Symbol, MainSignalState, SignalState,
+};
+use crate::signal_rules::SignalSystem;
+use crate::{TrackElement, TrackElementError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DistantSignalKind {
+    #[default]
+    Distant,
+    Repeater,
+}
+
+#[derive(Debug)]
+pub struct DistantSignal {
+    state: SignalState,
+    reported: Option<SignalState>,
+    id: String,
+    kind: DistantSignalKind,
+    main_state: SignalState,
+    main_system: Option<SignalSystem>,
+    carrier_stop: bool,
+}
+
+impl DistantSignal {
+    pub fn new(id: String, kind: DistantSignalKind) -> Self {
+        let mut signal = Self {
+            state: SignalState::default(),
+            reported: None,
+            id,
+            kind,
+            main_state: SignalState::default(),
+            main_system: None,
+            carrier_stop: false,
+        };
+        signal.refresh();
+        signal
+    }
+
+    pub fn new_arc(id: String, kind: DistantSignalKind) -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::new(id, kind)))
+    }
+
+    pub fn kind(&self) -> DistantSignalKind {
+        self.kind
+    }
+
+    pub fn report_state(&mut self, state: SignalState) {
+        self.reported = Some(state);
+        println!("Distant signal {} reports {:?}", self.id, state);
+    }
+
+    pub fn announce(&mut self, main_state: SignalState, main_system: Option<SignalSystem>) {
+        self.main_state = main_state;
+        self.main_system = main_system;
+        self.refresh();
+    }
+
+    pub fn carrier_changed(&mut self, carrier_state: SignalState) {
+        // A distant signal on the mast of a main signal is dark while that signal shows stop
+        self.carrier_stop = carrier_state.main().is_stop();
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        let state = if self.carrier_stop {
+            MainSignalState::Off.into()
+        } else {
+            self.indication()
+        };
+        if state != self.state {
+            self.state = state;
+            self.reported = None;
+            println!("Distant signal {} is now {:?}", self.id, self.state);
+        }
+    }
+
+    fn indication(&self) -> SignalState {
+        let main = self.main_state.main();
+        // Hp0 is shared by both systems, so only an undeclared system falls back to the aspect
+        let hv = match self.main_system {
+            Some(system) => system == SignalSystem::Hv,
+            None => matches!(
+                main,
+                MainSignalState::Hp0Hv | MainSignalState::Hp1 | MainSignalState::Hp2
+            ),
+        };
+        let indication = if hv {
+            match main {
+                MainSignalState::Hp1 => MainSignalState::Vr1.into(),
+                MainSignalState::Hp2 => MainSignalState::Vr2.into(),
+                _ => MainSignalState::Vr0.into(),
+            }
+        } else {
+            SignalState::ks(AdditionalSignalZs3Symbol::OFF, self.main_state)
+        };
+        if self.kind == DistantSignalKind::Repeater {
+            let main = match indication.main() {
+                MainSignalState::Ks2 => MainSignalState::Ks2WithAdditionalLight,
+                MainSignalState::Ks1Flashing => MainSignalState::Ks1FlashingWithAdditionalLight,
+                other => other,
+            };
+            SignalState::new(
+                main,
+                AdditionalSignalState::Off,
+                indication.zs3(),
+                indication.zs3v(),
+            )
+        } else {
+            indication
+        }
+    }
+}
+
+impl TrackElement for DistantSignal {
+    type State = SignalState;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> Self::State {
+        self.reported.unwrap_or(self.state)
+    }
+
+    fn commanded_state(&self) -> Self::State {
+        self.state
+    }
+
+    fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
+        self.state = new_state;
+        self.reported = None;
+        println!("Distant signal {} is now {:?}", self.id, self.state);
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+
+use crate::point::{Lockable, PointLock};
+use crate::{TrackElement, TrackElementError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DoubleSlipState {
+    #[default]
+    LeftLeft,
+    LeftRight,
+    RightLeft,
+    RightRight,
+}
+
+#[derive(Debug)]
+pub struct DoubleSlip {
+    state: DoubleSlipState,
+    reported: Option<DoubleSlipState>,
+    id: String,
+    locks: BTreeSet<PointLock>,
+}
+
+impl DoubleSlip {
+    pub fn new(state: DoubleSlipState, id: String) -> Self {
+        Self {
+            state,
+            reported: None,
+            id,
+            locks: BTreeSet::new(),
+        }
+    }
+
+    pub fn new_arc(state: DoubleSlipState, id: String) -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::new(state, id)))
+    }
+
+    pub fn report_state(&mut self, state: DoubleSlipState) {
+        self.reported = Some(state);
+        println!("Double slip {} reports {:?}", self.id, state);
+    }
+}
+
+impl Lockable for DoubleSlip {
+    fn locks(&self) -> &BTreeSet<PointLock> {
+        &self.locks
+    }
+
+    fn locks_mut(&mut self) -> &mut BTreeSet<PointLock> {
+        &mut self.locks
+    }
+}
+
+impl TrackElement for DoubleSlip {
+    type State = DoubleSlipState;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> Self::State {
+        self.reported.unwrap_or(self.state)
+    }
+
+    fn commanded_state(&self) -> Self::State {
+        self.state
+    }
+
+    fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
+        if self.is_locked() && new_state != self.state {
+            return Err(TrackElementError::PointLocked(self.id.clone()));
+        }
+        self.state = new_state;
+        self.reported = None;
+        println!("Double slip {} is now {:?}", self.id, self.state);
+        Ok(())
+    }
+}
@@ -1,137 +1,1443 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::iter::Iterator;
 use std::sync::Arc;
 use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
 use crate::{
-    point::{Point, PointState},
-    signal::{Signal, SignalState},
+    async_element::{confirm, Confirmable, Confirmation},
+    axle_counter::{AxleCounter, AxleCounterReset, AxleDirection},
+    clock::{Clock, SystemClock},
+    coupled_points::CoupledPoints,
+    crossing::{Crossing, CrossingState},
+    derailer::{Derailer, DerailerState},
+    double_slip::{DoubleSlip, DoubleSlipState},
+    level_crossing::{LevelCrossing, LevelCrossingState},
+    point::{Lockable, Point, PointLock, PointState},
+    signal::{AdditionalSignalZs3Symbol, Signal, SignalState},
+    signal_replacement::SignalReplacementRule,
+    three_way_point::{ThreeWayPoint, ThreeWayPointState},
     vacancy_section::{VacancySection, VacancySectionState},
 };
 use crate::{TrackElement, TrackElementError};
 
+pub type LevelCrossingSection = (Arc<RwLock<LevelCrossing>>, Arc<RwLock<VacancySection>>);
+
 #[derive(Debug, Clone)]
 pub struct DrivewayState {
     points: Vec<(Arc<RwLock<Point>>, PointState)>,
     signals: Vec<(Arc<RwLock<Signal>>, SignalState)>,
     vacancy_sections: Vec<(Arc<RwLock<VacancySection>>, VacancySectionState)>,
+    flank_points: Vec<(Arc<RwLock<Point>>, PointState)>,
+    flank_signals: Vec<(Arc<RwLock<Signal>>, SignalState)>,
+    double_slips: Vec<(Arc<RwLock<DoubleSlip>>, DoubleSlipState)>,
+    three_way_points: Vec<(Arc<RwLock<ThreeWayPoint>>, ThreeWayPointState)>,
+    crossings: Vec<(Arc<RwLock<Crossing>>, CrossingState)>,
+    coupled_points: Vec<(Arc<RwLock<CoupledPoints>>, PointState)>,
+    derailers: Vec<(Arc<RwLock<Derailer>>, DerailerState)>,
+    flank_derailers: Vec<(Arc<RwLock<Derailer>>, DerailerState)>,
+    level_crossings: Vec<LevelCrossingSection>,
+}
+
+impl DrivewayState {
+    pub fn new(
+        points: Vec<(Arc<RwLock<Point>>, PointState)>,
+        signals: Vec<(Arc<RwLock<Signal>>, SignalState)>,
+        vacancy_sections: Vec<(Arc<RwLock<VacancySection>>, VacancySectionState)>,
+    ) -> Self {
+        Self {
+            points,
+            signals,
+            vacancy_sections,
+            flank_points: Vec::new(),
+            flank_signals: Vec::new(),
+            double_slips: Vec::new(),
+            three_way_points: Vec::new(),
+            crossings: Vec::new(),
+            coupled_points: Vec::new(),
+            derailers: Vec::new(),
+            flank_derailers: Vec::new(),
+            level_crossings: Vec::new(),
+        }
+    }
+
+    pub fn double_slips(
+        mut self,
+        double_slips: Vec<(Arc<RwLock<DoubleSlip>>, DoubleSlipState)>,
+    ) -> Self {
+        self.double_slips = double_slips;
+        self
+    }
+
+    pub fn three_way_points(
+        mut self,
+        three_way_points: Vec<(Arc<RwLock<ThreeWayPoint>>, ThreeWayPointState)>,
+    ) -> Self {
+        self.three_way_points = three_way_points;
+        self
+    }
+
+    pub fn crossings(mut self, crossings: Vec<(Arc<RwLock<Crossing>>, CrossingState)>) -> Self {
+        self.crossings = crossings;
+        self
+    }
+
+    pub fn coupled_points(
+        mut self,
+        coupled_points: Vec<(Arc<RwLock<CoupledPoints>>, PointState)>,
+    ) -> Self {
+        self.coupled_points = coupled_points;
+        self
+    }
+
+    pub fn derailers(mut self, derailers: Vec<(Arc<RwLock<Derailer>>, DerailerState)>) -> Self {
+        self.derailers = derailers;
+        self
+    }
+
+    pub fn flank_derailers(
+        mut self,
+        flank_derailers: Vec<(Arc<RwLock<Derailer>>, DerailerState)>,
+    ) -> Self {
+        self.flank_derailers = flank_derailers;
+        self
+    }
+
+    pub fn level_crossings(mut self, level_crossings: Vec<LevelCrossingSection>) -> Self {
+        self.level_crossings = level_crossings;
+        self
+    }
+
+    pub fn flank_points(mut self, flank_points: Vec<(Arc<RwLock<Point>>, PointState)>) -> Self {
+        self.flank_points = flank_points;
+        self
+    }
+
+    pub fn flank_signals(mut self, flank_signals: Vec<(Arc<RwLock<Signal>>, SignalState)>) -> Self {
+        self.flank_signals = flank_signals;
+        self
+    }
+
+    pub fn set_state(&mut self) -> Result<(), TrackElementError> {
+        self.validate()?;
+        let previous = self.current_state();
+        if let Err(e) = self.set_elements().and_then(|_| self.set_signals()) {
+            previous.restore();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), TrackElementError> {
+        if let Some(element) = self.movable_elements().iter().find(|e| e.is_blocked()) {
+            return Err(TrackElementError::PointLocked(element.element_id()));
+        }
+        for (level_crossing, _) in &self.level_crossings {
+            let level_crossing = level_crossing.read().unwrap();
+            if level_crossing.state() == LevelCrossingState::Fault {
+                return Err(TrackElementError::LevelCrossingFault(
+                    level_crossing.id().to_string(),
+                ));
+            }
+        }
+        for (signal, state) in self.signals.iter().chain(&self.flank_signals) {
+            let signal = signal.read().unwrap();
+            if !signal.supports(*state) {
+                return Err(TrackElementError::UnsupportedSignalState(
+                    signal.id().to_string(),
+                    *state,
+                ));
+            }
+        }
+        self.check_vacancy()
+    }
+
+    pub fn check_vacancy(&self) -> Result<(), TrackElementError> {
+        let violations: Vec<_> = self
+            .vacancy_sections
+            .iter()
+            .filter_map(|(section, _)| {
+                let section = section.read().unwrap();
+                let state = section.state();
+                (state != VacancySectionState::Free).then(|| (section.id().to_string(), state))
+            })
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(TrackElementError::VacancySectionsNotFree(violations))
+        }
+    }
+
+    fn current_state(&self) -> DrivewayState {
+        DrivewayState::new(
+            snapshot(&self.points),
+            snapshot(&self.signals),
+            snapshot(&self.vacancy_sections),
+        )
+        .flank_points(snapshot(&self.flank_points))
+        .flank_signals(snapshot(&self.flank_signals))
+        .double_slips(snapshot(&self.double_slips))
+        .three_way_points(snapshot(&self.three_way_points))
+        .crossings(snapshot(&self.crossings))
+        .coupled_points(snapshot(&self.coupled_points))
+        .derailers(snapshot(&self.derailers))
+        .flank_derailers(snapshot(&self.flank_derailers))
+        // Only crossings that are not closed yet have to be reopened on rollback
+        .level_crossings(
+            self.level_crossings
+                .iter()
+                .filter(|(lc, _)| lc.read().unwrap().state() != LevelCrossingState::Closed)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    fn restore(&self) {
+        restore(&self.points);
+        restore(&self.signals);
+        restore(&self.flank_points);
+        restore(&self.flank_signals);
+        restore(&self.double_slips);
+        restore(&self.three_way_points);
+        restore(&self.crossings);
+        restore(&self.coupled_points);
+        restore(&self.derailers);
+        restore(&self.flank_derailers);
+        for (level_crossing, _) in &self.level_crossings {
+            let _ = level_crossing
+                .write()
+                .unwrap()
+                .set_state(LevelCrossingState::Open);
+        }
+    }
+
+    fn set_elements(&self) -> Result<(), TrackElementError> {
+        apply(&self.flank_signals)?;
+        apply(&self.flank_points)?;
+        apply(&self.flank_derailers)?;
+        apply(&self.points)?;
+        apply(&self.double_slips)?;
+        apply(&self.three_way_points)?;
+        apply(&self.crossings)?;
+        apply(&self.coupled_points)?;
+        apply(&self.derailers)?;
+        for (level_crossing, _) in &self.level_crossings {
+            let mut level_crossing = level_crossing.write().unwrap();
+            if let Err(e) = level_crossing.set_state(LevelCrossingState::Closed) {
+                return Err(TrackElementError::ElementFailed(
+                    level_crossing.id().to_string(),
+                    Box::new(e),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn set_signals(&self) -> Result<(), TrackElementError> {
+        apply(&self.signals)
+    }
+
+    fn route_elements(&self) -> Vec<&dyn MovableTarget> {
+        let mut elements: Vec<&dyn MovableTarget> = Vec::new();
+        elements.extend(self.points.iter().map(|e| e as &dyn MovableTarget));
+        elements.extend(self.double_slips.iter().map(|e| e as &dyn MovableTarget));
+        elements.extend(
+            self.three_way_points
+                .iter()
+                .map(|e| e as &dyn MovableTarget),
+        );
+        elements.extend(self.crossings.iter().map(|e| e as &dyn MovableTarget));
+        elements.extend(self.coupled_points.iter().map(|e| e as &dyn MovableTarget));
+        elements.extend(self.derailers.iter().map(|e| e as &dyn MovableTarget));
+        elements
+    }
+
+    fn movable_elements(&self) -> Vec<&dyn MovableTarget> {
+        let mut elements = self.route_elements();
+        elements.extend(self.flank_points.iter().map(|e| e as &dyn MovableTarget));
+        elements.extend(self.flank_derailers.iter().map(|e| e as &dyn MovableTarget));
+        elements
+    }
+
+    fn elements_confirmed(&self) -> bool {
+        self.movable_elements().iter().all(|e| e.is_reached())
+            && self
+                .level_crossings
+                .iter()
+                .all(|(lc, _)| lc.read().unwrap().state() == LevelCrossingState::Closed)
+    }
+
+    fn element_confirmations(&self) -> Vec<Confirmation> {
+        let mut confirmations: Vec<_> = self
+            .flank_signals
+            .iter()
+            .map(|(s, state)| Confirmation::new(s.clone(), *state))
+            .collect();
+        confirmations.extend(self.movable_elements().iter().map(|e| e.confirmation()));
+        confirmations.extend(
+            self.level_crossings
+                .iter()
+                .map(|(lc, _)| Confirmation::new(lc.clone(), LevelCrossingState::Closed)),
+        );
+        confirmations
+    }
+
+    fn signal_confirmations(&self) -> Vec<Confirmation> {
+        self.signals
+            .iter()
+            .map(|(s, state)| Confirmation::new(s.clone(), *state))
+            .collect()
+    }
+
+    fn join(mut self, mut other: DrivewayState) -> Self {
+        self.points.append(&mut other.points);
+        self.signals.append(&mut other.signals);
+        self.vacancy_sections.append(&mut other.vacancy_sections);
+        self.flank_points.append(&mut other.flank_points);
+        self.flank_signals.append(&mut other.flank_signals);
+        self.double_slips.append(&mut other.double_slips);
+        self.three_way_points.append(&mut other.three_way_points);
+        self.crossings.append(&mut other.crossings);
+        self.coupled_points.append(&mut other.coupled_points);
+        self.derailers.append(&mut other.derailers);
+        self.flank_derailers.append(&mut other.flank_derailers);
+        self.level_crossings.append(&mut other.level_crossings);
+        self
+    }
+
+    fn has_conflicting_flank_protection(&self, other: &DrivewayState) -> bool {
+        let flank = positions(&self.flank_points);
+        let other_flank = positions(&other.flank_points);
+        let flank_derailers = positions(&self.flank_derailers);
+        let other_flank_derailers = positions(&other.flank_derailers);
+        let flank_signals = positions(&self.flank_signals);
+        let other_flank_signals = positions(&other.flank_signals);
+        differ(&flank, &other_flank)
+            || differ(&flank, &other.point_positions())
+            || differ(&other_flank, &self.point_positions())
+            || differ(&flank_derailers, &other_flank_derailers)
+            || differ(&flank_derailers, &positions(&other.derailers))
+            || differ(&other_flank_derailers, &positions(&self.derailers))
+            || differ(&flank_signals, &other_flank_signals)
+            || differ(&flank_signals, &positions(&other.signals))
+            || differ(&other_flank_signals, &positions(&self.signals))
+    }
+
+    fn point_positions(&self) -> Vec<(String, PointState)> {
+        let mut positions = positions(&self.points);
+        for (coupled, state) in &self.coupled_points {
+            for point in coupled.read().unwrap().points() {
+                positions.push((point.read().unwrap().id().to_string(), *state));
+            }
+        }
+        positions
+    }
+
+    pub fn points(&self) -> &[(Arc<RwLock<Point>>, PointState)] {
+        self.points.as_ref()
+    }
+
+    pub fn signals(&self) -> &[(Arc<RwLock<Signal>>, SignalState)] {
+        self.signals.as_ref()
+    }
+
+    pub fn vacancy_sections(&self) -> &[(Arc<RwLock<VacancySection>>, VacancySectionState)] {
+        self.vacancy_sections.as_ref()
+    }
+
+    pub fn flank_protection_points(&self) -> &[(Arc<RwLock<Point>>, PointState)] {
+        self.flank_points.as_ref()
+    }
+
+    pub fn flank_protection_signals(&self) -> &[(Arc<RwLock<Signal>>, SignalState)] {
+        self.flank_signals.as_ref()
+    }
+
+    pub fn double_slip_states(&self) -> &[(Arc<RwLock<DoubleSlip>>, DoubleSlipState)] {
+        self.double_slips.as_ref()
+    }
+
+    pub fn three_way_point_states(&self) -> &[(Arc<RwLock<ThreeWayPoint>>, ThreeWayPointState)] {
+        self.three_way_points.as_ref()
+    }
+
+    pub fn crossing_states(&self) -> &[(Arc<RwLock<Crossing>>, CrossingState)] {
+        self.crossings.as_ref()
+    }
+
+    pub fn coupled_point_states(&self) -> &[(Arc<RwLock<CoupledPoints>>, PointState)] {
+        self.coupled_points.as_ref()
+    }
+
+    pub fn derailer_states(&self) -> &[(Arc<RwLock<Derailer>>, DerailerState)] {
+        self.derailers.as_ref()
+    }
+
+    pub fn flank_protection_derailers(&self) -> &[(Arc<RwLock<Derailer>>, DerailerState)] {
+        self.flank_derailers.as_ref()
+    }
+
+    pub fn level_crossing_sections(&self) -> &[LevelCrossingSection] {
+        self.level_crossings.as_ref()
+    }
+}
+
+fn positions<T: TrackElement>(elements: &[(Arc<RwLock<T>>, T::State)]) -> Vec<(String, T::State)> {
+    elements
+        .iter()
+        .map(|(elem, state)| (elem.read().unwrap().id().to_string(), *state))
+        .collect()
+}
+
+fn differ<S: PartialEq>(a: &[(String, S)], b: &[(String, S)]) -> bool {
+    a.iter()
+        .any(|(id, state)| b.iter().any(|(o, other)| id == o && state != other))
+}
+
+trait Supervised: Lockable {
+    fn deviation(&self) -> DrivewayDeviation;
+
+    fn member_ids(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl Supervised for Point {
+    fn deviation(&self) -> DrivewayDeviation {
+        DrivewayDeviation::PointPosition(self.id().to_string(), self.reported_state())
+    }
+}
+
+impl Supervised for DoubleSlip {
+    fn deviation(&self) -> DrivewayDeviation {
+        DrivewayDeviation::DoubleSlipPosition(self.id().to_string(), self.reported_state())
+    }
+}
+
+impl Supervised for ThreeWayPoint {
+    fn deviation(&self) -> DrivewayDeviation {
+        DrivewayDeviation::ThreeWayPointPosition(self.id().to_string(), self.reported_state())
+    }
+}
+
+impl Supervised for CoupledPoints {
+    fn deviation(&self) -> DrivewayDeviation {
+        DrivewayDeviation::CoupledPointsPosition(self.id().to_string(), self.reported_state())
+    }
+
+    fn member_ids(&self) -> Vec<String> {
+        self.points()
+            .iter()
+            .map(|p| p.read().unwrap().id().to_string())
+            .collect()
+    }
+}
+
+impl Supervised for Derailer {
+    fn deviation(&self) -> DrivewayDeviation {
+        DrivewayDeviation::DerailerPosition(self.id().to_string(), self.reported_state())
+    }
+}
+
+impl Supervised for Crossing {
+    fn deviation(&self) -> DrivewayDeviation {
+        DrivewayDeviation::CrossingTrack(self.id().to_string(), self.reported_state())
+    }
+}
+
+trait MovableTarget {
+    fn element_id(&self) -> String;
+    fn element_ids(&self) -> Vec<String>;
+    fn is_reached(&self) -> bool;
+    fn is_blocked(&self) -> bool;
+    fn lock(&self, lock: &PointLock);
+    fn unlock(&self, lock: &PointLock);
+    fn deviation(&self) -> Option<DrivewayDeviation>;
+    fn confirmation(&self) -> Confirmation;
+}
+
+impl<T: Supervised + Confirmable + Send + Sync + 'static> MovableTarget
+    for (Arc<RwLock<T>>, T::State)
+{
+    fn element_id(&self) -> String {
+        self.0.read().unwrap().id().to_string()
+    }
+
+    fn element_ids(&self) -> Vec<String> {
+        let mut ids = vec![self.element_id()];
+        ids.extend(self.0.read().unwrap().member_ids());
+        ids
+    }
+
+    fn is_reached(&self) -> bool {
+        self.0.read().unwrap().reported_state() == self.1
+    }
+
+    fn is_blocked(&self) -> bool {
+        let element = self.0.read().unwrap();
+        element.is_locked() && element.commanded_state() != self.1
+    }
+
+    fn lock(&self, lock: &PointLock) {
+        self.0.write().unwrap().lock(lock.clone());
+    }
+
+    fn unlock(&self, lock: &PointLock) {
+        self.0.write().unwrap().unlock(lock);
+    }
+
+    fn deviation(&self) -> Option<DrivewayDeviation> {
+        let element = self.0.read().unwrap();
+        (element.reported_state() != self.1).then(|| element.deviation())
+    }
+
+    fn confirmation(&self) -> Confirmation {
+        Confirmation::new(self.0.clone(), self.1)
+    }
+}
+
+fn snapshot<T: TrackElement>(
+    elements: &[(Arc<RwLock<T>>, T::State)],
+) -> Vec<(Arc<RwLock<T>>, T::State)> {
+    elements
+        .iter()
+        .map(|(elem, _)| (elem.clone(), elem.read().unwrap().commanded_state()))
+        .collect()
+}
+
+fn apply<T: TrackElement>(
+    elements: &[(Arc<RwLock<T>>, T::State)],
+) -> Result<(), TrackElementError> {
+    for (elem, state) in elements {
+        let mut elem = elem.write().unwrap();
+        if let Err(e) = elem.set_state(*state) {
+            return Err(TrackElementError::ElementFailed(
+                elem.id().to_string(),
+                Box::new(e),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn restore<T: TrackElement>(elements: &[(Arc<RwLock<T>>, T::State)]) {
+    for (elem, state) in elements {
+        // Keep restoring the remaining elements even if one of them refuses
+        let _ = elem.write().unwrap().set_state(*state);
+    }
+}
+
+impl PartialEq for DrivewayState {
+    fn eq(&self, other: &Self) -> bool {
+        for ((point_a, _), (point_b, _)) in self.points.iter().zip(other.points.clone()) {
+            let a = point_a.read().unwrap();
+            let b = point_b.read().unwrap();
+            if a.id() != b.id() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Overlap {
+    id: String,
+    points: Vec<(Arc<RwLock<Point>>, PointState)>,
+    vacancy_sections: Vec<Arc<RwLock<VacancySection>>>,
+    release_delay: Duration,
+}
+
+impl Overlap {
+    pub fn new(
+        id: String,
+        points: Vec<(Arc<RwLock<Point>>, PointState)>,
+        vacancy_sections: Vec<Arc<RwLock<VacancySection>>>,
+        release_delay: Duration,
+    ) -> Self {
+        Self {
+            id,
+            points,
+            vacancy_sections,
+            release_delay,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn points(&self) -> &[(Arc<RwLock<Point>>, PointState)] {
+        self.points.as_ref()
+    }
+
+    pub fn vacancy_sections(&self) -> &[Arc<RwLock<VacancySection>>] {
+        self.vacancy_sections.as_ref()
+    }
+
+    pub fn release_delay(&self) -> Duration {
+        self.release_delay
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.vacancy_sections
+            .iter()
+            .all(|v| v.read().unwrap().state() == VacancySectionState::Free)
+    }
+
+    fn state(&self) -> DrivewayState {
+        DrivewayState::new(
+            self.points.clone(),
+            Vec::new(),
+            self.vacancy_sections
+                .iter()
+                .map(|v| (v.clone(), VacancySectionState::Free))
+                .collect(),
+        )
+    }
+
+    fn element_ids(&self) -> Vec<String> {
+        let points = self
+            .points
+            .iter()
+            .map(|(p, _)| p.read().unwrap().id().to_string());
+        let sections = self
+            .vacancy_sections
+            .iter()
+            .map(|v| v.read().unwrap().id().to_string());
+        points.chain(sections).collect()
+    }
+
+    fn contains(&self, element_id: &str) -> bool {
+        self.element_ids().iter().any(|id| id == element_id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DrivewayStatus {
+    #[default]
+    Idle,
+    Requested,
+    ElementsCommanded,
+    ElementsConfirmed,
+    Locked,
+    SignalCleared,
+    Occupied,
+    Released,
+}
+
+impl DrivewayStatus {
+    pub fn is_set(&self) -> bool {
+        !matches!(self, DrivewayStatus::Idle | DrivewayStatus::Released)
+    }
+
+    pub fn can_transition_to(&self, next: DrivewayStatus) -> bool {
+        use DrivewayStatus::*;
+        matches!(
+            (self, next),
+            (Idle | Released, Requested)
+                | (Requested, ElementsCommanded)
+                | (ElementsCommanded, ElementsConfirmed)
+                | (ElementsConfirmed, Locked)
+                | (Locked, SignalCleared)
+                | (SignalCleared, Locked)
+                | (SignalCleared, Occupied)
+                | (Requested | Locked, Idle)
+                | (
+                    Requested
+                        | ElementsCommanded
+                        | ElementsConfirmed
+                        | Locked
+                        | SignalCleared
+                        | Occupied,
+                    Released
+                )
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DrivewayDeviation {
+    PointPosition(String, PointState),
+    DoubleSlipPosition(String, DoubleSlipState),
+    ThreeWayPointPosition(String, ThreeWayPointState),
+    CrossingTrack(String, CrossingState),
+    CoupledPointsPosition(String, PointState),
+    DerailerPosition(String, DerailerState),
+    LevelCrossingState(String, LevelCrossingState),
+    VacancySection(String, VacancySectionState),
+    SignalState(String, SignalState),
+}
+
+#[derive(Debug)]
+pub struct Driveway {
+    conflicting_driveways: Vec<Arc<RwLock<Driveway>>>,
+    overlap_conflicting_driveways: Vec<Arc<RwLock<Driveway>>>,
+    status: DrivewayStatus,
+    target_state: DrivewayState,
+    start_signal: Arc<RwLock<Signal>>,
+    end_signal: Arc<RwLock<Signal>>,
+    previous_state: Option<DrivewayState>,
+    section_points: BTreeMap<String, Vec<String>>,
+    occupied_sections: usize,
+    released_sections: usize,
+    released_points: BTreeSet<String>,
+    passing_level_crossings: BTreeSet<String>,
+    reopened_level_crossings: BTreeSet<String>,
+    overlaps: Vec<Overlap>,
+    overlap: Option<usize>,
+    overlap_release_at: Option<Instant>,
+    hold_signal: bool,
+    speed: AdditionalSignalZs3Symbol,
+    line_speed: Option<u16>,
+    release_at: Option<Instant>,
+    clock: Arc<dyn Clock>,
 }
 
-impl DrivewayState {
-    pub fn new(
-        points: Vec<(Arc<RwLock<Point>>, PointState)>,
-        signals: Vec<(Arc<RwLock<Signal>>, SignalState)>,
-        vacancy_sections: Vec<(Arc<RwLock<VacancySection>>, VacancySectionState)>,
-    ) -> Self {
-        Self {
-            points,
-            signals,
-            vacancy_sections,
+impl Driveway {
+    pub fn new(
+        conflicting_driveways: Vec<Arc<RwLock<Driveway>>>,
+        expected_state: DrivewayState,
+        start_signal: Arc<RwLock<Signal>>,
+        end_signal: Arc<RwLock<Signal>>,
+    ) -> Self {
+        Self {
+            conflicting_driveways,
+            overlap_conflicting_driveways: Vec::new(),
+            status: DrivewayStatus::Idle,
+            target_state: expected_state,
+            start_signal,
+            end_signal,
+            previous_state: None,
+            section_points: BTreeMap::new(),
+            occupied_sections: 0,
+            released_sections: 0,
+            released_points: BTreeSet::new(),
+            passing_level_crossings: BTreeSet::new(),
+            reopened_level_crossings: BTreeSet::new(),
+            overlaps: Vec::new(),
+            overlap: None,
+            overlap_release_at: None,
+            hold_signal: false,
+            speed: AdditionalSignalZs3Symbol::OFF,
+            line_speed: None,
+            release_at: None,
+            clock: SystemClock::new_arc(),
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn id(&self) -> String {
+        format!(
+            "{}-{}",
+            self.start_signal.read().unwrap().id(),
+            self.end_signal.read().unwrap().id()
+        )
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.status.is_set()
+    }
+
+    pub fn status(&self) -> DrivewayStatus {
+        self.status
+    }
+
+    fn transition(&mut self, next: DrivewayStatus) -> Result<(), TrackElementError> {
+        if self.status.can_transition_to(next) {
+            self.status = next;
+            Ok(())
+        } else {
+            Err(TrackElementError::InvalidDrivewayTransition(
+                self.status,
+                next,
+            ))
+        }
+    }
+
+    pub fn set_way(&mut self) -> Result<(), TrackElementError> {
+        if self.has_conflicting_driveways() {
+            return Err(TrackElementError::HasConflictingDriveways);
+        }
+        let overlap = if self.overlaps.is_empty() {
+            None
+        } else {
+            Some(
+                self.overlaps
+                    .iter()
+                    .position(|o| self.is_overlap_available(o))
+                    .ok_or_else(|| TrackElementError::NoOverlapAvailable(self.id()))?,
+            )
+        };
+        self.request(overlap)
+    }
+
+    pub async fn set_way_async(&mut self) -> Result<(), TrackElementError> {
+        self.set_way()?;
+        if let Err(e) = confirm(self.element_confirmations()).await {
+            self.cancel()?;
+            return Err(e);
+        }
+        self.update()?;
+        if let Err(e) = confirm(self.signal_confirmations()).await {
+            self.replace_signal()?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn element_confirmations(&self) -> Vec<Confirmation> {
+        self.commanded_state().element_confirmations()
+    }
+
+    fn signal_confirmations(&self) -> Vec<Confirmation> {
+        if self.status == DrivewayStatus::SignalCleared {
+            self.chained_state()
+                .unwrap_or_else(|_| self.target_state.clone())
+                .signal_confirmations()
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn set_way_with_overlap(&mut self, overlap_id: &str) -> Result<(), TrackElementError> {
+        if self.has_conflicting_driveways() {
+            return Err(TrackElementError::HasConflictingDriveways);
+        }
+        let overlap = self
+            .overlaps
+            .iter()
+            .position(|o| o.id() == overlap_id)
+            .ok_or_else(|| TrackElementError::OverlapDoesNotExist(overlap_id.to_string()))?;
+        if !self.is_overlap_available(&self.overlaps[overlap]) {
+            return Err(TrackElementError::NoOverlapAvailable(self.id()));
+        }
+        self.request(Some(overlap))
+    }
+
+    fn request(&mut self, overlap: Option<usize>) -> Result<(), TrackElementError> {
+        self.transition(DrivewayStatus::Requested)?;
+        self.reset_progress();
+        self.overlap = overlap;
+        let commanded = self.commanded_state();
+        let previous = commanded.current_state();
+        if let Err(e) = commanded.validate().and_then(|_| commanded.set_elements()) {
+            previous.restore();
+            self.reset_progress();
+            self.transition(DrivewayStatus::Idle)?;
+            return Err(e);
+        }
+        self.lock_level_crossings();
+        self.previous_state = Some(previous);
+        self.transition(DrivewayStatus::ElementsCommanded)?;
+        self.update()
+    }
+
+    fn commanded_state(&self) -> DrivewayState {
+        match self.selected_overlap() {
+            Some(overlap) => self.target_state.clone().join(overlap.state()),
+            None => self.target_state.clone(),
+        }
+    }
+
+    fn reset_progress(&mut self) {
+        self.previous_state = None;
+        self.occupied_sections = 0;
+        self.released_sections = 0;
+        self.released_points.clear();
+        self.passing_level_crossings.clear();
+        self.reopened_level_crossings.clear();
+        self.overlap = None;
+        self.overlap_release_at = None;
+        self.hold_signal = false;
+        self.release_at = None;
+    }
+
+    pub fn cancel(&mut self) -> Result<(), TrackElementError> {
+        // Once a train has entered, only sectional release may free the route
+        if self.status == DrivewayStatus::Occupied {
+            return Err(TrackElementError::InvalidDrivewayTransition(
+                self.status,
+                DrivewayStatus::Released,
+            ));
+        }
+        self.release()
+    }
+
+    fn release(&mut self) -> Result<(), TrackElementError> {
+        self.transition(DrivewayStatus::Released)?;
+        self.start_signal.write().unwrap().reset();
+        self.unlock_points(|_| true);
+        self.open_level_crossings()?;
+        self.reset_progress();
+        Ok(())
+    }
+
+    fn lock_points(&self) {
+        let lock = PointLock::Driveway(self.id());
+        let commanded = self.commanded_state();
+        for element in commanded.movable_elements() {
+            element.lock(&lock);
+        }
+    }
+
+    fn unlock_points(&self, filter: impl Fn(&str) -> bool) {
+        let lock = PointLock::Driveway(self.id());
+        let commanded = self.commanded_state();
+        for element in commanded.movable_elements() {
+            if filter(&element.element_id()) {
+                element.unlock(&lock);
+            }
+        }
+    }
+
+    fn update_occupancy_locks(&self) {
+        for (section, _) in &self.target_state.vacancy_sections {
+            let section = section.read().unwrap();
+            let Some(point_ids) = self.section_points.get(section.id()) else {
+                continue;
+            };
+            let lock = PointLock::Occupancy(section.id().to_string());
+            let occupied = section.state() == VacancySectionState::Occupied;
+            let commanded = self.commanded_state();
+            for element in commanded.movable_elements() {
+                if !point_ids.contains(&element.element_id()) {
+                    continue;
+                }
+                if occupied {
+                    element.lock(&lock);
+                } else {
+                    element.unlock(&lock);
+                }
+            }
+        }
+    }
+
+    pub fn set_overlaps(&mut self, overlaps: &mut Vec<Overlap>) {
+        self.overlaps.append(overlaps);
+    }
+
+    pub fn overlaps(&self) -> &[Overlap] {
+        self.overlaps.as_ref()
+    }
+
+    pub fn selected_overlap(&self) -> Option<&Overlap> {
+        self.overlap.map(|i| &self.overlaps[i])
+    }
+
+    fn is_overlap_available(&self, overlap: &Overlap) -> bool {
+        overlap.is_free()
+            && !overlap.points.iter().any(|(p, _)| {
+                let id = p.read().unwrap().id().to_string();
+                self.conflicting_driveways
+                    .iter()
+                    .chain(&self.overlap_conflicting_driveways)
+                    .any(|d| d.read().unwrap().holds(&id))
+            })
+    }
+
+    fn release_overlap(&mut self) {
+        if self.overlap.is_none() {
+            return;
+        }
+        if self.occupied_sections < self.target_state.vacancy_sections.len() {
+            return;
+        }
+        let now = self.clock.now();
+        match self.overlap_release_at {
+            None => {
+                let delay = self.selected_overlap().unwrap().release_delay;
+                self.overlap_release_at = Some(now + delay);
+            }
+            Some(release_at) if now >= release_at => {
+                let lock = PointLock::Driveway(self.id());
+                for (point, _) in self.selected_overlap().unwrap().points() {
+                    point.write().unwrap().unlock(&lock);
+                }
+                self.overlap = None;
+                self.overlap_release_at = None;
+            }
+            _ => (),
+        }
+    }
+
+    pub fn cancel_delayed(&mut self, delay: Duration) -> Result<(), TrackElementError> {
+        if !self.is_set() || self.status == DrivewayStatus::Occupied {
+            return Err(TrackElementError::InvalidDrivewayTransition(
+                self.status,
+                DrivewayStatus::Released,
+            ));
+        }
+        self.replace_signal()?;
+        self.release_at = Some(self.clock.now() + delay);
+        Ok(())
+    }
+
+    fn replace_signal(&mut self) -> Result<(), TrackElementError> {
+        self.start_signal.write().unwrap().reset();
+        self.hold_signal = true;
+        if self.status == DrivewayStatus::SignalCleared {
+            self.transition(DrivewayStatus::Locked)?;
+        }
+        Ok(())
+    }
+
+    pub fn release_pending(&self) -> bool {
+        self.release_at.is_some()
+    }
+
+    pub fn update(&mut self) -> Result<(), TrackElementError> {
+        self.update_occupancy_locks();
+        if let Some(release_at) = self.release_at {
+            if self.clock.now() >= release_at {
+                self.cancel()?;
+            }
+            return Ok(());
+        }
+        if self.status == DrivewayStatus::ElementsCommanded
+            && self.commanded_state().elements_confirmed()
+        {
+            self.transition(DrivewayStatus::ElementsConfirmed)?;
+        }
+        if self.status == DrivewayStatus::ElementsConfirmed {
+            self.lock_points();
+            self.transition(DrivewayStatus::Locked)?;
+        }
+        if self.status == DrivewayStatus::Locked && !self.hold_signal {
+            if let Err(e) = self.chained_state().and_then(|state| state.set_signals()) {
+                self.unlock_points(|_| true);
+                self.unlock_level_crossings();
+                if let Some(previous) = self.previous_state.take() {
+                    previous.restore();
+                }
+                self.reset_progress();
+                self.transition(DrivewayStatus::Idle)?;
+                return Err(e);
+            }
+            self.transition(DrivewayStatus::SignalCleared)?;
+        }
+        if matches!(
+            self.status,
+            DrivewayStatus::SignalCleared | DrivewayStatus::Occupied
+        ) {
+            let sections = &self.target_state.vacancy_sections;
+            while let Some((section, _)) = sections.get(self.occupied_sections) {
+                if section.read().unwrap().state() != VacancySectionState::Occupied {
+                    break;
+                }
+                self.occupied_sections += 1;
+            }
+        }
+        if self.status == DrivewayStatus::SignalCleared && self.occupied_sections > 0 {
+            // The train has passed the start signal, so a following train must not
+            self.start_signal.write().unwrap().reset();
+            self.transition(DrivewayStatus::Occupied)?;
+        }
+        if self.status == DrivewayStatus::SignalCleared {
+            self.update_start_aspect()?;
+        }
+        if self.status == DrivewayStatus::Occupied {
+            self.update_level_crossings()?;
+            self.release_overlap();
+            self.release_passed_sections()?;
+        }
+        Ok(())
+    }
+
+    pub fn set_speed(&mut self, speed: AdditionalSignalZs3Symbol) {
+        self.speed = speed;
+    }
+
+    pub fn speed(&self) -> AdditionalSignalZs3Symbol {
+        self.speed
+    }
+
+    pub fn set_line_speed(&mut self, line_speed: u16) {
+        self.line_speed = Some(line_speed);
+    }
+
+    pub fn line_speed(&self) -> Option<u16> {
+        self.line_speed
+    }
+
+    pub fn permitted_speed(&self) -> Option<u16> {
+        let points = self
+            .target_state
+            .points
+            .iter()
+            .map(|(point, target)| (point.clone(), *target));
+        let coupled = self
+            .target_state
+            .coupled_points
+            .iter()
+            .flat_map(|(coupled, target)| {
+                let coupled = coupled.read().unwrap();
+                let points: Vec<_> = coupled
+                    .points()
+                    .iter()
+                    .map(|p| (p.clone(), *target))
+                    .collect();
+                points
+            });
+        points
+            .chain(coupled)
+            .filter_map(|(point, target)| point.read().unwrap().speed_limit(target))
+            .chain(self.line_speed)
+            .min()
+    }
+
+    fn signalled_speed(
+        &self,
+        configured: AdditionalSignalZs3Symbol,
+    ) -> Result<AdditionalSignalZs3Symbol, TrackElementError> {
+        let requested = if self.speed != AdditionalSignalZs3Symbol::OFF {
+            self.speed
+        } else {
+            configured
+        };
+        // Zs3 is only shown where the route is slower than the line
+        match self.permitted_speed() {
+            Some(permitted) if Some(permitted) != self.line_speed => {
+                let limit = AdditionalSignalZs3Symbol::from_speed(permitted)
+                    .ok_or(TrackElementError::SpeedNotSignallable(permitted))?;
+                // OFF is encoded above every speed, so the points' limit always caps it
+                Ok(if (requested as u8) < (limit as u8) {
+                    requested
+                } else {
+                    limit
+                })
+            }
+            _ => Ok(requested),
+        }
+    }
+
+    fn chained_state(&self) -> Result<DrivewayState, TrackElementError> {
+        let mut state = self.target_state.clone();
+        let start_id = self.start_signal.read().unwrap().id().to_string();
+        for (signal, target) in &mut state.signals {
+            let signal = signal.read().unwrap();
+            if signal.id() == start_id {
+                *target = self.start_aspect(&signal, *target)?;
+            }
+        }
+        Ok(state)
+    }
+
+    fn start_aspect(
+        &self,
+        signal: &Signal,
+        configured: SignalState,
+    ) -> Result<SignalState, TrackElementError> {
+        if !configured.main().is_ks() {
+            return Ok(configured);
+        }
+        let speed = self.signalled_speed(configured.zs3())?;
+        let aspect = SignalState::ks(speed, self.end_signal.read().unwrap().state());
+        // Falling back to the configured aspect could show more than the route permits
+        if !signal.supports(aspect) {
+            return Err(TrackElementError::UnsupportedSignalState(
+                signal.id().to_string(),
+                aspect,
+            ));
+        }
+        Ok(aspect)
+    }
+
+    fn update_start_aspect(&mut self) -> Result<(), TrackElementError> {
+        let state = match self.chained_state() {
+            Ok(state) => state,
+            Err(e) => {
+                // The start signal falls back to stop before the caller learns why
+                self.replace_signal()?;
+                return Err(e);
+            }
+        };
+        for (signal, target) in &state.signals {
+            if !Arc::ptr_eq(signal, &self.start_signal) {
+                continue;
+            }
+            let mut signal = signal.write().unwrap();
+            // Never clear a signal again that was replaced in the meantime
+            let current = signal.commanded_state();
+            if current.main().is_ks() && current != *target {
+                signal.set_state(*target)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn update_level_crossings(&mut self) -> Result<(), TrackElementError> {
+        for (level_crossing, section) in &self.target_state.level_crossings {
+            let mut level_crossing = level_crossing.write().unwrap();
+            let id = level_crossing.id().to_string();
+            if self.reopened_level_crossings.contains(&id) {
+                continue;
+            }
+            match section.read().unwrap().state() {
+                VacancySectionState::Occupied => {
+                    self.passing_level_crossings.insert(id);
+                }
+                VacancySectionState::Free if self.passing_level_crossings.contains(&id) => {
+                    self.open_level_crossing(&mut level_crossing)?;
+                    self.reopened_level_crossings.insert(id);
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn open_level_crossings(&self) -> Result<(), TrackElementError> {
+        for (level_crossing, _) in &self.target_state.level_crossings {
+            let mut level_crossing = level_crossing.write().unwrap();
+            if !self.reopened_level_crossings.contains(level_crossing.id()) {
+                self.open_level_crossing(&mut level_crossing)?;
+            }
         }
+        Ok(())
     }
 
-    pub fn set_state(&mut self) -> Result<(), TrackElementError> {
-        for (elem, state) in &self.points {
-            elem.write().unwrap().set_state(*state)?;
+    fn open_level_crossing(
+        &self,
+        level_crossing: &mut LevelCrossing,
+    ) -> Result<(), TrackElementError> {
+        level_crossing.unlock(&PointLock::Driveway(self.id()));
+        if !level_crossing.is_locked() {
+            level_crossing.set_state(LevelCrossingState::Open)?;
         }
+        Ok(())
+    }
 
-        // Set signals and rollback in case there was a failure
-        if self
-            .signals
-            .iter()
-            .map(|(elem, state)| elem.write().unwrap().set_state(*state))
-            .any(|r| r.is_err())
-        {
-            self.signals
-                .iter()
-                .for_each(|(elem, _)| elem.write().unwrap().reset())
+    fn lock_level_crossings(&self) {
+        let lock = PointLock::Driveway(self.id());
+        for (level_crossing, _) in &self.target_state.level_crossings {
+            level_crossing.write().unwrap().lock(lock.clone());
         }
+    }
 
-        for (section, state) in &self.vacancy_sections {
-            section.write().unwrap().set_state(*state)?;
+    fn unlock_level_crossings(&self) {
+        let lock = PointLock::Driveway(self.id());
+        for (level_crossing, _) in &self.target_state.level_crossings {
+            level_crossing.write().unwrap().unlock(&lock);
         }
+    }
 
+    fn release_passed_sections(&mut self) -> Result<(), TrackElementError> {
+        while self.released_sections < self.occupied_sections {
+            let (section, _) = &self.target_state.vacancy_sections[self.released_sections];
+            let section = section.read().unwrap();
+            if section.state() != VacancySectionState::Free {
+                break;
+            }
+            if let Some(points) = self.section_points.get(section.id()) {
+                self.unlock_points(|id| points.iter().any(|p| p == id));
+                self.released_points.extend(points.iter().cloned());
+            }
+            self.released_sections += 1;
+        }
+        if self.released_sections == self.target_state.vacancy_sections.len() {
+            self.release()?;
+        }
         Ok(())
     }
 
-    fn join(mut self, mut other: DrivewayState) -> Self {
-        self.points.append(&mut other.points);
-        self.signals.append(&mut other.signals);
-        self.vacancy_sections.append(&mut other.vacancy_sections);
-        self
+    pub fn set_section_points(&mut self, section_id: &str, point_ids: Vec<String>) {
+        self.section_points
+            .insert(section_id.to_string(), point_ids);
     }
 
-    pub fn points(&self) -> &[(Arc<RwLock<Point>>, PointState)] {
-        self.points.as_ref()
+    pub fn released_sections(&self) -> usize {
+        self.released_sections
     }
 
-    pub fn signals(&self) -> &[(Arc<RwLock<Signal>>, SignalState)] {
-        self.signals.as_ref()
+    pub fn holds(&self, element_id: &str) -> bool {
+        if !self.is_set() {
+            return false;
+        }
+        let holds_point = self
+            .target_state
+            .route_elements()
+            .iter()
+            .filter(|e| !self.released_points.contains(&e.element_id()))
+            .any(|e| e.element_ids().iter().any(|id| id == element_id));
+        let holds_section = self
+            .target_state
+            .vacancy_sections
+            .iter()
+            .skip(self.released_sections)
+            .any(|(v, _)| v.read().unwrap().id() == element_id);
+        let holds_flank = self
+            .target_state
+            .flank_points
+            .iter()
+            .any(|(p, _)| p.read().unwrap().id() == element_id)
+            || self
+                .target_state
+                .flank_signals
+                .iter()
+                .any(|(s, _)| s.read().unwrap().id() == element_id)
+            || self
+                .target_state
+                .flank_derailers
+                .iter()
+                .any(|(d, _)| d.read().unwrap().id() == element_id);
+        let holds_overlap = self
+            .selected_overlap()
+            .map(|o| o.contains(element_id))
+            .unwrap_or(false);
+        holds_point || holds_section || holds_flank || holds_overlap
     }
 
-    pub fn vacancy_sections(&self) -> &[(Arc<RwLock<VacancySection>>, VacancySectionState)] {
-        self.vacancy_sections.as_ref()
+    fn point_ids(&self) -> Vec<String> {
+        self.target_state
+            .route_elements()
+            .into_iter()
+            .flat_map(|e| e.element_ids())
+            .collect()
     }
-}
 
-impl PartialEq for DrivewayState {
-    fn eq(&self, other: &Self) -> bool {
-        for ((point_a, _), (point_b, _)) in self.points.iter().zip(other.points.clone()) {
-            let a = point_a.read().unwrap();
-            let b = point_b.read().unwrap();
-            if a.id() != b.id() {
-                return false;
+    fn overlap_point_ids(&self) -> Vec<String> {
+        self.overlaps
+            .iter()
+            .flat_map(|o| &o.points)
+            .map(|(p, _)| p.read().unwrap().id().to_string())
+            .collect()
+    }
+
+    fn element_ids(&self) -> Vec<String> {
+        let points = self
+            .target_state
+            .route_elements()
+            .into_iter()
+            .flat_map(|e| e.element_ids());
+        let sections = self
+            .target_state
+            .vacancy_sections
+            .iter()
+            .map(|(v, _)| v.read().unwrap().id().to_string());
+        let signals = self
+            .target_state
+            .signals
+            .iter()
+            .chain(&self.target_state.flank_signals)
+            .map(|(s, _)| s.read().unwrap().id().to_string());
+        let flank = self
+            .target_state
+            .flank_points
+            .iter()
+            .map(|(p, _)| p.read().unwrap().id().to_string())
+            .chain(
+                self.target_state
+                    .flank_derailers
+                    .iter()
+                    .map(|(d, _)| d.read().unwrap().id().to_string()),
+            );
+        points.chain(sections).chain(signals).chain(flank).collect()
+    }
+
+    pub fn supervise(&mut self) -> Result<Vec<DrivewayDeviation>, TrackElementError> {
+        if !matches!(
+            self.status,
+            DrivewayStatus::Locked | DrivewayStatus::SignalCleared | DrivewayStatus::Occupied
+        ) {
+            return Ok(Vec::new());
+        }
+        let state = self.state();
+        let mut deviations = Vec::new();
+
+        for element in self.target_state.movable_elements() {
+            if self.released_points.contains(&element.element_id()) {
+                continue;
+            }
+            if let Some(deviation) = element.deviation() {
+                deviations.push(deviation);
             }
         }
-        true
-    }
-}
 
-#[derive(Debug)]
-pub struct Driveway {
-    conflicting_driveways: Vec<Arc<RwLock<Driveway>>>,
-    is_set: bool,
-    target_state: DrivewayState,
-    start_signal: Arc<RwLock<Signal>>,
-    end_signal: Arc<RwLock<Signal>>,
-}
+        for (level_crossing, _) in &self.target_state.level_crossings {
+            let level_crossing = level_crossing.read().unwrap();
+            if self.reopened_level_crossings.contains(level_crossing.id()) {
+                continue;
+            }
+            if level_crossing.state() != LevelCrossingState::Closed {
+                deviations.push(DrivewayDeviation::LevelCrossingState(
+                    level_crossing.id().to_string(),
+                    level_crossing.state(),
+                ));
+            }
+        }
 
-impl Driveway {
-    pub fn new(
-        conflicting_driveways: Vec<Arc<RwLock<Driveway>>>,
-        expected_state: DrivewayState,
-        start_signal: Arc<RwLock<Signal>>,
-        end_signal: Arc<RwLock<Signal>>,
-    ) -> Self {
-        Self {
-            conflicting_driveways,
-            is_set: false,
-            target_state: expected_state,
-            start_signal,
-            end_signal,
+        for (signal, target) in &self.target_state.flank_signals {
+            let signal = signal.read().unwrap();
+            if signal.reported_state() != *target {
+                deviations.push(DrivewayDeviation::SignalState(
+                    signal.id().to_string(),
+                    signal.reported_state(),
+                ));
+            }
         }
-    }
 
-    pub fn id(&self) -> String {
-        format!(
-            "{}-{}",
-            self.start_signal.read().unwrap().id(),
-            self.end_signal.read().unwrap().id()
-        )
-    }
+        for (i, (section, actual)) in state
+            .vacancy_sections()
+            .iter()
+            .enumerate()
+            .skip(self.released_sections)
+        {
+            let in_order = i < self.occupied_sections;
+            let is_deviation = match actual {
+                VacancySectionState::Free => false,
+                VacancySectionState::Occupied => !in_order,
+                VacancySectionState::Disturbed | VacancySectionState::CommunicationError => true,
+            };
+            if is_deviation {
+                let id = section.read().unwrap().id().to_string();
+                deviations.push(DrivewayDeviation::VacancySection(id, *actual));
+            }
+        }
 
-    pub fn is_set(&self) -> bool {
-        self.is_set
-    }
+        if let Some(overlap) = self.selected_overlap() {
+            for (point, target) in overlap.points() {
+                let point = point.read().unwrap();
+                if point.reported_state() != *target {
+                    deviations.push(DrivewayDeviation::PointPosition(
+                        point.id().to_string(),
+                        point.reported_state(),
+                    ));
+                }
+            }
+            if self.status != DrivewayStatus::Occupied {
+                for section in overlap.vacancy_sections() {
+                    let section = section.read().unwrap();
+                    if section.reported_state() != VacancySectionState::Free {
+                        deviations.push(DrivewayDeviation::VacancySection(
+                            section.id().to_string(),
+                            section.reported_state(),
+                        ));
+                    }
+                }
+            }
+        }
 
-    pub fn set_way(&mut self) -> Result<(), TrackElementError> {
-        if self.has_conflicting_driveways() {
-            Err(TrackElementError::HasConflictingDriveways)
-        } else {
-            self.target_state.set_state()?;
-            self.is_set = true;
-            Ok(())
+        if self.status == DrivewayStatus::SignalCleared {
+            let expected = self
+                .chained_state()
+                .unwrap_or_else(|_| self.target_state.clone());
+            for ((signal, target), (_, actual)) in expected.signals.iter().zip(state.signals()) {
+                if actual != target {
+                    let id = signal.read().unwrap().id().to_string();
+                    deviations.push(DrivewayDeviation::SignalState(id, *actual));
+                }
+            }
+        }
+
+        if !deviations.is_empty() {
+            self.replace_signal()?;
         }
+        Ok(deviations)
     }
 
     pub fn state(&self) -> DrivewayState {
@@ -139,44 +1445,41 @@ impl Driveway {
             .target_state
             .signals
             .iter()
-            .map(|(s, _)| (s.clone(), s.read().unwrap().state()))
+            .map(|(s, _)| (s.clone(), s.read().unwrap().reported_state()))
             .collect();
         signals.push((
             self.end_signal.clone(),
-            self.end_signal.read().unwrap().state(),
+            self.end_signal.read().unwrap().reported_state(),
         ));
-        for (vacancy_section, _) in &self.target_state.vacancy_sections {
-            for sig in vacancy_section.read().unwrap().previous_signals() {
-                if !signals
-                    .iter()
-                    .any(|(s, _)| s.read().unwrap().id() == sig.read().unwrap().id())
-                {
-                    signals.push((sig.clone(), sig.read().unwrap().state()));
-                }
-            }
-        }
 
         let vacancy_sections: Vec<_> = self
             .target_state
             .vacancy_sections
             .iter()
-            .map(|(s, _)| (s.clone(), s.read().unwrap().state()))
+            .map(|(s, _)| (s.clone(), s.read().unwrap().reported_state()))
             .collect();
 
         let points: Vec<_> = self
             .target_state
             .points
             .iter()
-            .map(|(s, _)| (s.clone(), s.read().unwrap().state()))
+            .map(|(s, _)| (s.clone(), s.read().unwrap().reported_state()))
             .collect();
 
         DrivewayState::new(points, signals, vacancy_sections)
     }
 
     fn has_conflicting_driveways(&self) -> bool {
-        self.conflicting_driveways
-            .iter()
-            .any(|d| d.read().unwrap().is_set())
+        let element_ids = self.element_ids();
+        let holds_any = |d: &Driveway| element_ids.iter().any(|id| d.holds(id));
+        // Driveways that only share overlap points conflict while they hold the shared ones
+        self.conflicting_driveways.iter().any(|d| {
+            let d = d.read().unwrap();
+            d.is_set() && (d.released_sections == 0 || holds_any(&d))
+        }) || self.overlap_conflicting_driveways.iter().any(|d| {
+            let d = d.read().unwrap();
+            d.is_set() && holds_any(&d)
+        })
     }
 
     pub fn set_conflicting_driveways(&mut self, driveways: &mut Vec<Arc<RwLock<Driveway>>>) {
@@ -186,11 +1489,29 @@ impl Driveway {
 
 pub struct DrivewayManager {
     driveways: BTreeMap<String, Arc<RwLock<Driveway>>>,
+    replacement_rules: Vec<SignalReplacementRule>,
+    axle_counters: Vec<Arc<RwLock<AxleCounter>>>,
 }
 
 impl DrivewayManager {
     pub fn new(driveways: BTreeMap<String, Arc<RwLock<Driveway>>>) -> Self {
-        Self { driveways }
+        Self {
+            driveways,
+            replacement_rules: Vec::new(),
+            axle_counters: Vec::new(),
+        }
+    }
+
+    pub fn add_axle_counter(&mut self, axle_counter: Arc<RwLock<AxleCounter>>) {
+        self.axle_counters.push(axle_counter);
+    }
+
+    pub fn set_replacement_rules(&mut self, rules: Vec<SignalReplacementRule>) {
+        self.replacement_rules = rules;
+    }
+
+    pub fn replacement_rules(&self) -> &[SignalReplacementRule] {
+        &self.replacement_rules
     }
 
     pub fn get(&self, uuid: &str) -> Option<Arc<RwLock<Driveway>>> {
@@ -233,6 +1554,131 @@ impl DrivewayManager {
         start_signal_id: &str,
         end_signal_id: &str,
     ) -> Result<(), TrackElementError> {
+        let driveway = self.find_driveway(start_signal_id, end_signal_id)?;
+        driveway.write().unwrap().set_way()?;
+        self.update()
+    }
+
+    pub async fn set_driveway_async(
+        &self,
+        start_signal_id: &str,
+        end_signal_id: &str,
+    ) -> Result<(), TrackElementError> {
+        // Never hold the driveway lock across an await point
+        let driveway = self.find_driveway(start_signal_id, end_signal_id)?;
+        driveway.write().unwrap().set_way()?;
+        let confirmations = driveway.read().unwrap().element_confirmations();
+        if let Err(e) = confirm(confirmations).await {
+            driveway.write().unwrap().cancel()?;
+            return Err(e);
+        }
+        driveway.write().unwrap().update()?;
+        let confirmations = driveway.read().unwrap().signal_confirmations();
+        if let Err(e) = confirm(confirmations).await {
+            driveway.write().unwrap().replace_signal()?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn set_driveway_with_overlap(
+        &self,
+        start_signal_id: &str,
+        end_signal_id: &str,
+        overlap_id: &str,
+    ) -> Result<(), TrackElementError> {
+        let driveway = self.find_driveway(start_signal_id, end_signal_id)?;
+        driveway.write().unwrap().set_way_with_overlap(overlap_id)?;
+        self.update()
+    }
+
+    pub fn release_driveway(
+        &self,
+        start_signal_id: &str,
+        end_signal_id: &str,
+    ) -> Result<(), TrackElementError> {
+        let driveway = self.find_driveway(start_signal_id, end_signal_id)?;
+        driveway.write().unwrap().cancel()?;
+        self.update()
+    }
+
+    pub fn release_driveway_delayed(
+        &self,
+        start_signal_id: &str,
+        end_signal_id: &str,
+        delay: Duration,
+    ) -> Result<(), TrackElementError> {
+        let driveway = self.find_driveway(start_signal_id, end_signal_id)?;
+        driveway.write().unwrap().cancel_delayed(delay)?;
+        Ok(())
+    }
+
+    pub fn driveway_status(
+        &self,
+        start_signal_id: &str,
+        end_signal_id: &str,
+    ) -> Result<DrivewayStatus, TrackElementError> {
+        let driveway = self.find_driveway(start_signal_id, end_signal_id)?;
+        let status = driveway.read().unwrap().status();
+        Ok(status)
+    }
+
+    pub fn driveway_statuses(&self) -> BTreeMap<String, DrivewayStatus> {
+        self.driveways
+            .iter()
+            .map(|(id, dw)| (id.clone(), dw.read().unwrap().status()))
+            .collect()
+    }
+
+    pub fn update(&self) -> Result<(), TrackElementError> {
+        // A failing driveway must not keep the others from being updated
+        let errors: Vec<_> = self
+            .driveways
+            .iter()
+            .filter_map(|(id, driveway)| {
+                let result = driveway.write().unwrap().update();
+                result.err().map(|e| (id.clone(), e))
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(TrackElementError::DrivewaysFailed(errors))
+        }
+    }
+
+    pub fn supervise(&self) -> Result<BTreeMap<String, Vec<DrivewayDeviation>>, TrackElementError> {
+        let mut deviations = BTreeMap::new();
+        let mut errors = Vec::new();
+        for (id, driveway) in &self.driveways {
+            let mut driveway = driveway.write().unwrap();
+            let result = driveway.update().and_then(|_| driveway.supervise());
+            match result {
+                Ok(found) if found.is_empty() => (),
+                Ok(found) => {
+                    deviations.insert(id.clone(), found);
+                }
+                Err(e) => {
+                    // A driveway that cannot be supervised must not stay cleared
+                    if driveway.is_set() {
+                        let _ = driveway.replace_signal();
+                    }
+                    errors.push((id.clone(), e));
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(deviations)
+        } else {
+            Err(TrackElementError::SupervisionFailed(deviations, errors))
+        }
+    }
+
+    fn find_driveway(
+        &self,
+        start_signal_id: &str,
+        end_signal_id: &str,
+    ) -> Result<Arc<RwLock<Driveway>>, TrackElementError> {
         let id = DrivewayManager::driveway_id(start_signal_id, end_signal_id);
 
         let driveway = match self.get(&id) {
@@ -266,8 +1712,7 @@ impl DrivewayManager {
             }
         };
 
-        driveway.write().unwrap().set_way()?;
-        Ok(())
+        Ok(driveway)
     }
 
     fn driveway_id(a: &str, b: &str) -> String {
@@ -295,38 +1740,145 @@ impl DrivewayManager {
                 let are_continuous =
                     self_start.id() == other_end.id() || other_start.id() == self_end.id();
 
-                let driveway_points = &driveway.target_state.points;
-                let other_points = &other.target_state.points;
+                let driveway_points = driveway.point_ids();
+                let other_points = other.point_ids();
+                let driveway_overlap_points = driveway.overlap_point_ids();
+                let other_overlap_points = other.overlap_point_ids();
                 let driveway_signals = &driveway.target_state.signals;
                 let other_signals = &other.target_state.signals;
-                let has_conflicting_points = driveway_points.iter().any(|(e, _)| {
-                    other_points
-                        .iter()
-                        .any(|(o, _)| e.read().unwrap().id() == o.read().unwrap().id())
-                });
+                let has_conflicting_points =
+                    driveway_points.iter().any(|e| other_points.contains(e));
+                let has_conflicting_flank = driveway
+                    .target_state
+                    .has_conflicting_flank_protection(&other.target_state);
                 let has_conflicting_signals = driveway_signals.iter().any(|(e, _)| {
                     other_signals.iter().any(|(o, _)| {
                         e.read().unwrap().id() == o.read().unwrap().id() && !are_continuous
                     })
                 });
 
-                if has_conflicting_points || has_conflicting_signals {
+                let has_conflicting_overlaps = driveway_overlap_points
+                    .iter()
+                    .any(|e| other_points.contains(e) || other_overlap_points.contains(e))
+                    || other_overlap_points
+                        .iter()
+                        .any(|e| driveway_points.contains(e));
+
+                if has_conflicting_points || has_conflicting_signals || has_conflicting_flank {
                     driveway.conflicting_driveways.push(other_arc);
+                } else if has_conflicting_overlaps {
+                    driveway.overlap_conflicting_driveways.push(other_arc);
                 }
             }
         }
     }
 
     pub fn is_allocated(&self, vacancy_section: &VacancySection) -> bool {
-        if let Some(driveway) = self.driveways.values().find(|dw| {
-            let dw = dw.read().unwrap();
-            let vs = dw.target_state.vacancy_sections();
-            vs.iter()
-                .any(|(v, _)| v.read().unwrap().id() == vacancy_section.id())
-        }) {
-            driveway.read().unwrap().is_set()
-        } else {
-            false
+        self.driveways
+            .values()
+            .any(|dw| dw.read().unwrap().holds(vacancy_section.id()))
+    }
+
+    pub fn set_vacancy_section_state(
+        &self,
+        section_id: &str,
+        new_state: VacancySectionState,
+    ) -> Result<(), TrackElementError> {
+        let section = self.find_vacancy_section(section_id)?;
+        let previous_state = section.read().unwrap().state();
+        section.write().unwrap().set_state(new_state)?;
+        self.replace_signals(section_id, previous_state, new_state)?;
+        self.update()
+    }
+
+    pub fn replace_signals(
+        &self,
+        section_id: &str,
+        from: VacancySectionState,
+        to: VacancySectionState,
+    ) -> Result<(), TrackElementError> {
+        let set_driveways: Vec<_> = self
+            .driveways
+            .iter()
+            .filter(|(_, dw)| dw.read().unwrap().is_set())
+            .map(|(id, _)| id.clone())
+            .collect();
+        for rule in &self.replacement_rules {
+            if rule.applies_to(section_id, from, to, &set_driveways) {
+                rule.apply()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn reset_vacancy_section(
+        &self,
+        section_id: &str,
+        operator: &str,
+    ) -> Result<(), TrackElementError> {
+        let section = self.find_vacancy_section(section_id)?;
+        let previous_state = section.read().unwrap().state();
+        section.write().unwrap().reset(operator)?;
+        self.replace_signals(section_id, previous_state, VacancySectionState::Free)?;
+        self.update()
+    }
+
+    pub fn count_axle(
+        &self,
+        counter_id: &str,
+        head_id: &str,
+        direction: AxleDirection,
+    ) -> Result<(), TrackElementError> {
+        self.update_axle_counter(counter_id, |counter| counter.count(head_id, direction))
+    }
+
+    pub fn reset_axle_counter(
+        &self,
+        counter_id: &str,
+        reset: AxleCounterReset,
+        operator: &str,
+    ) -> Result<(), TrackElementError> {
+        self.update_axle_counter(counter_id, |counter| counter.reset(reset, operator))
+    }
+
+    fn update_axle_counter(
+        &self,
+        counter_id: &str,
+        f: impl FnOnce(&mut AxleCounter) -> Result<(), TrackElementError>,
+    ) -> Result<(), TrackElementError> {
+        let counter = self
+            .axle_counters
+            .iter()
+            .find(|c| c.read().unwrap().id() == counter_id)
+            .ok_or_else(|| TrackElementError::AxleCounterDoesNotExist(counter_id.to_string()))?;
+        let section = counter.read().unwrap().section().clone();
+        let section_id = section.read().unwrap().id().to_string();
+        let previous_state = section.read().unwrap().state();
+        f(&mut counter.write().unwrap())?;
+        let new_state = section.read().unwrap().state();
+        if new_state != previous_state {
+            self.replace_signals(&section_id, previous_state, new_state)?;
         }
+        self.update()
+    }
+
+    fn find_vacancy_section(
+        &self,
+        section_id: &str,
+    ) -> Result<Arc<RwLock<VacancySection>>, TrackElementError> {
+        self.driveways
+            .values()
+            .find_map(|dw| {
+                dw.read()
+                    .unwrap()
+                    .target_state
+                    .vacancy_sections()
+                    .iter()
+                    .find(|(v, _)| v.read().unwrap().id() == section_id)
+                    .map(|(v, _)| v.clone())
+            })
+            .ok_or(TrackElementError::VacancySectionDoesNotExist(
+                section_id.to_string(),
+            ))
     }
 }
@@ -0,0 +1,118 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, SystemClock};
+use crate::point::{Lockable, PointLock};
+use crate::{TrackElement, TrackElementError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LevelCrossingState {
+    #[default]
+    Open,
+    Closing,
+    Closed,
+    Fault,
+}
+
+#[derive(Debug)]
+pub struct LevelCrossing {
+    state: LevelCrossingState,
+    id: String,
+    closing_time: Duration,
+    closing_until: Option<Instant>,
+    fault: bool,
+    locks: BTreeSet<PointLock>,
+    clock: Arc<dyn Clock>,
+}
+
+impl LevelCrossing {
+    pub fn new(id: String, closing_time: Duration) -> Self {
+        Self {
+            state: LevelCrossingState::Open,
+            id,
+            closing_time,
+            closing_until: None,
+            fault: false,
+            locks: BTreeSet::new(),
+            clock: SystemClock::new_arc(),
+        }
+    }
+
+    pub fn new_arc(id: String, closing_time: Duration) -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::new(id, closing_time)))
+    }
+
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn closing_time(&self) -> Duration {
+        self.closing_time
+    }
+
+    pub fn report_fault(&mut self) {
+        self.fault = true;
+        println!("Level crossing {} reports a fault", self.id);
+    }
+
+    pub fn repair(&mut self) {
+        self.fault = false;
+    }
+}
+
+impl Lockable for LevelCrossing {
+    fn locks(&self) -> &BTreeSet<PointLock> {
+        &self.locks
+    }
+
+    fn locks_mut(&mut self) -> &mut BTreeSet<PointLock> {
+        &mut self.locks
+    }
+}
+
+impl TrackElement for LevelCrossing {
+    type State = LevelCrossingState;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> Self::State {
+        if self.fault {
+            return LevelCrossingState::Fault;
+        }
+        match self.closing_until {
+            Some(until) if self.clock.now() < until => LevelCrossingState::Closing,
+            _ => self.state,
+        }
+    }
+
+    fn commanded_state(&self) -> Self::State {
+        self.state
+    }
+
+    fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
+        // Every driveway over the crossing keeps it closed until its train has passed
+        if self.is_locked() && new_state != self.state {
+            return Err(TrackElementError::PointLocked(self.id.clone()));
+        }
+        match new_state {
+            LevelCrossingState::Open => {
+                self.closing_until = None;
+            }
+            LevelCrossingState::Closed => {
+                if self.state == LevelCrossingState::Closed {
+                    return Ok(());
+                }
+                self.closing_until = Some(self.clock.now() + self.closing_time);
+            }
+            LevelCrossingState::Closing | LevelCrossingState::Fault => {
+                return Err(TrackElementError::InvalidLevelCrossingState(new_state));
+            }
+        }
+        self.state = new_state;
+        println!("Level crossing {} is now {:?}", self.id, self.state);
+        Ok(())
+    }
+}
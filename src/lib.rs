@@ -1,9 +1,30 @@
-use signal::MainSignalState;
+use std::collections::BTreeMap;
 
+use driveway::{DrivewayDeviation, DrivewayStatus};
+use level_crossing::LevelCrossingState;
+use point::PointState;
+use signal::{MainSignalState, SignalState};
+use signal_rules::SignalRule;
+use vacancy_section::VacancySectionState;
+
+pub mod additional_signal;
+pub mod async_element;
+pub mod axle_counter;
+pub mod backend;
+pub mod clock;
 pub mod control_station;
+pub mod coupled_points;
+pub mod crossing;
+pub mod derailer;
+pub mod distant_signal;
+pub mod double_slip;
 pub mod driveway;
+pub mod level_crossing;
 pub mod point;
 pub mod signal;
+pub mod signal_replacement;
+pub mod signal_rules;
+pub mod three_way_point;
 
 #[cfg(test)]
 mod test;
@@ -12,9 +33,33 @@ pub mod vacancy_section;
 #[derive(Debug)]
 pub enum TrackElementError {
     DrivewayDoesNotExist(String),
+    VacancySectionDoesNotExist(String),
+    AxleCounterHeadDoesNotExist(String),
+    AxleCounterDoesNotExist(String),
+    OverlapDoesNotExist(String),
+    NoOverlapAvailable(String),
     HasConflictingDriveways,
     InvalidAdditionalSignalState,
+    SpeedNotSignallable(u16),
+    InvalidSignalCode(u8),
+    PointLocked(String),
+    InvalidPointState(PointState),
+    InvalidLevelCrossingState(LevelCrossingState),
+    LevelCrossingFault(String),
     InvalidMainSignalState(MainSignalState),
+    InvalidDrivewayTransition(DrivewayStatus, DrivewayStatus),
+    UnsupportedSignalState(String, SignalState),
+    ElementFailed(String, Box<TrackElementError>),
+    BackendFailed(String),
+    ConfirmationTimeout(String),
+    VacancySectionsNotFree(Vec<(String, VacancySectionState)>),
+    DrivewaysFailed(Vec<(String, TrackElementError)>),
+    SupervisionFailed(
+        BTreeMap<String, Vec<DrivewayDeviation>>,
+        Vec<(String, TrackElementError)>,
+    ),
+    VacancySectionResetRequired(String),
+    SignalRuleViolated(String, SignalRule),
 }
 
 impl std::fmt::Display for TrackElementError {
@@ -26,9 +71,21 @@ impl std::fmt::Display for TrackElementError {
 impl std::error::Error for TrackElementError {}
 
 pub trait TrackElement {
-    type State: Copy + Default;
+    type State: Copy + Default + PartialEq + std::fmt::Debug + Send + Sync + 'static;
 
     fn id(&self) -> &str;
     fn state(&self) -> Self::State;
     fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError>;
+
+    fn commanded_state(&self) -> Self::State {
+        self.state()
+    }
+
+    fn reported_state(&self) -> Self::State {
+        self.state()
+    }
+
+    fn has_mismatch(&self) -> bool {
+        self.commanded_state() != self.reported_state()
+    }
 }
@@ -1,16 +1,47 @@
+use std::collections::BTreeSet;
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
+use crate::backend::{Backend, SimulationBackend};
+use crate::clock::{Clock, SystemClock};
 use crate::{TrackElement, TrackElementError};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum PointState {
+    #[default]
     Left,
     Right,
+    Moving,
+    NoEndPosition,
 }
 
-impl Default for PointState {
-    fn default() -> Self {
-        PointState::Left
+impl PointState {
+    pub fn is_end_position(&self) -> bool {
+        matches!(self, PointState::Left | PointState::Right)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PointLock {
+    Driveway(String),
+    Operator,
+    Occupancy(String),
+}
+
+pub trait Lockable: TrackElement {
+    fn locks(&self) -> &BTreeSet<PointLock>;
+    fn locks_mut(&mut self) -> &mut BTreeSet<PointLock>;
+
+    fn lock(&mut self, lock: PointLock) {
+        self.locks_mut().insert(lock);
+    }
+
+    fn unlock(&mut self, lock: &PointLock) {
+        self.locks_mut().remove(lock);
+    }
+
+    fn is_locked(&self) -> bool {
+        !self.locks().is_empty()
     }
 }
 
@@ -18,16 +49,80 @@ impl Default for PointState {
 pub struct Point {
     state: PointState,
     id: String,
+    locks: BTreeSet<PointLock>,
+    throw_time: Duration,
+    moving_until: Option<Instant>,
+    end_position_lost: bool,
+    clock: Arc<dyn Clock>,
+    backend: Arc<dyn Backend<PointState>>,
+    diverging_speed: Option<(PointState, u16)>,
 }
 
 impl Point {
     pub fn new(state: PointState, id: String) -> Self {
-        Self { state, id }
+        Self {
+            state,
+            id,
+            locks: BTreeSet::new(),
+            throw_time: Duration::ZERO,
+            moving_until: None,
+            end_position_lost: false,
+            clock: SystemClock::new_arc(),
+            backend: SimulationBackend::new_arc(),
+            diverging_speed: None,
+        }
     }
 
     pub fn new_arc(state: PointState, id: String) -> Arc<RwLock<Self>> {
         Arc::new(RwLock::new(Self::new(state, id)))
     }
+
+    pub fn set_throw_time(&mut self, throw_time: Duration) {
+        self.throw_time = throw_time;
+    }
+
+    pub fn throw_time(&self) -> Duration {
+        self.throw_time
+    }
+
+    pub fn set_diverging_speed(&mut self, position: PointState, speed: u16) {
+        self.diverging_speed = Some((position, speed));
+    }
+
+    pub fn speed_limit(&self, position: PointState) -> Option<u16> {
+        self.diverging_speed
+            .filter(|(diverging, _)| *diverging == position)
+            .map(|(_, speed)| speed)
+    }
+
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn set_backend(&mut self, backend: Arc<dyn Backend<PointState>>) {
+        self.backend = backend;
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.moving_until
+            .map(|until| self.clock.now() < until)
+            .unwrap_or(false)
+    }
+
+    pub fn lose_end_position(&mut self) {
+        self.end_position_lost = true;
+        println!("Point {} lost its end position", self.id);
+    }
+}
+
+impl Lockable for Point {
+    fn locks(&self) -> &BTreeSet<PointLock> {
+        &self.locks
+    }
+
+    fn locks_mut(&mut self) -> &mut BTreeSet<PointLock> {
+        &mut self.locks
+    }
 }
 
 impl TrackElement for Point {
@@ -38,11 +133,37 @@ impl TrackElement for Point {
     }
 
     fn state(&self) -> Self::State {
+        if self.end_position_lost {
+            PointState::NoEndPosition
+        } else if self.is_moving() {
+            PointState::Moving
+        } else {
+            self.backend.status(&self.id).unwrap_or(self.state)
+        }
+    }
+
+    fn commanded_state(&self) -> Self::State {
         self.state
     }
 
     fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
+        if !new_state.is_end_position() {
+            return Err(TrackElementError::InvalidPointState(new_state));
+        }
+        if self.is_locked() && new_state != self.state {
+            return Err(TrackElementError::PointLocked(self.id.clone()));
+        }
+        if self.state == new_state && !self.end_position_lost {
+            return Ok(());
+        }
+        self.backend.command(&self.id, new_state)?;
         self.state = new_state;
+        self.end_position_lost = false;
+        self.moving_until = if self.throw_time.is_zero() {
+            None
+        } else {
+            Some(self.clock.now() + self.throw_time)
+        };
         println!("Point state is now {:?}", self.state);
         Ok(())
     }
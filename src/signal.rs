@@ -1,5 +1,10 @@
 use std::sync::{Arc, RwLock};
 
+use crate::additional_signal::AdditionalSignalZs3;
+pub use crate::additional_signal::AdditionalSignalZs3Symbol;
+use crate::backend::{Backend, SimulationBackend};
+use crate::distant_signal::DistantSignal;
+use crate::signal_rules::SignalSystem;
 use crate::{TrackElement, TrackElementError};
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
@@ -36,12 +41,56 @@ pub enum AdditionalSignalState {
     Off = 0xFF,
 }
 
+impl TryFrom<u8> for MainSignalState {
+    type Error = TrackElementError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(MainSignalState::Hp0),
+            0x02 => Ok(MainSignalState::Hp0PlusSh1),
+            0x03 => Ok(MainSignalState::Hp0WithDrivingIndicator),
+            0x04 => Ok(MainSignalState::Ks1),
+            0x05 => Ok(MainSignalState::Ks1Flashing),
+            0x06 => Ok(MainSignalState::Ks1FlashingWithAdditionalLight),
+            0x07 => Ok(MainSignalState::Ks2),
+            0x08 => Ok(MainSignalState::Ks2WithAdditionalLight),
+            0x09 => Ok(MainSignalState::Sh1),
+            0x0A => Ok(MainSignalState::IdLight),
+            0xA0 => Ok(MainSignalState::Hp0Hv),
+            0xA1 => Ok(MainSignalState::Hp1),
+            0xA2 => Ok(MainSignalState::Hp2),
+            0xB0 => Ok(MainSignalState::Vr0),
+            0xB1 => Ok(MainSignalState::Vr1),
+            0xB2 => Ok(MainSignalState::Vr2),
+            0xFF => Ok(MainSignalState::Off),
+            _ => Err(TrackElementError::InvalidSignalCode(value)),
+        }
+    }
+}
+
+impl TryFrom<u8> for AdditionalSignalState {
+    type Error = TrackElementError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(AdditionalSignalState::Zs1),
+            0x02 => Ok(AdditionalSignalState::Zs7),
+            0x03 => Ok(AdditionalSignalState::Zs8),
+            0x04 => Ok(AdditionalSignalState::Zs6),
+            0x05 => Ok(AdditionalSignalState::Zs13),
+            0xFF => Ok(AdditionalSignalState::Off),
+            _ => Err(TrackElementError::InvalidSignalCode(value)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SupportedSignalStates {
     main: Vec<MainSignalState>,
     additional: Vec<AdditionalSignalState>,
     zs3: Vec<AdditionalSignalZs3Symbol>,
     zs3v: Vec<AdditionalSignalZs3Symbol>,
+    system: Option<SignalSystem>,
 }
 
 impl Default for SupportedSignalStates {
@@ -51,6 +100,7 @@ impl Default for SupportedSignalStates {
             additional: vec![AdditionalSignalState::Off],
             zs3: vec![AdditionalSignalZs3Symbol::OFF],
             zs3v: vec![AdditionalSignalZs3Symbol::OFF],
+            system: None,
         }
     }
 }
@@ -67,9 +117,19 @@ impl SupportedSignalStates {
             additional,
             zs3,
             zs3v,
+            system: None,
         }
     }
 
+    pub fn system(mut self, system: SignalSystem) -> Self {
+        self.system = Some(system);
+        self
+    }
+
+    pub fn signal_system(&self) -> Option<SignalSystem> {
+        self.system
+    }
+
     pub fn main(mut self, main: &mut Vec<MainSignalState>) -> Self {
         self.main.append(main);
         self
@@ -90,60 +150,13 @@ impl SupportedSignalStates {
         self
     }
 
+    #[deprecated(note = "use Signal::supports, which also considers mounted Zs3/Zs3v indicators")]
     pub fn is_signal_state_supported(&self, state: SignalState) -> bool {
         self.main.contains(&state.main)
             && self.additional.contains(&state.additional)
             && self.zs3.contains(&state.zs3)
             && self.zs3v.contains(&state.zs3v)
-    }
-}
-
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub enum AdditionalSignalZs3Symbol {
-    #[default]
-    OFF = 0xFF,
-    ONE = 1,
-    TWO = 2,
-    THREE = 3,
-    FOUR = 4,
-    FIVE = 5,
-    SIX = 6,
-    SEVEN = 7,
-    EIGHT = 8,
-    NINE = 9,
-    TEN = 10,
-    ELEVEN = 11,
-    TWELVE = 12,
-    THIRTEEN = 13,
-    FOURTEEN = 14,
-    FIFTEEN = 15,
-    SIXTEEN = 16,
-}
-
-impl TryFrom<u8> for AdditionalSignalZs3Symbol {
-    type Error = TrackElementError;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            0xFF | 0 => Ok(AdditionalSignalZs3Symbol::OFF),
-            1 => Ok(AdditionalSignalZs3Symbol::ONE),
-            2 => Ok(AdditionalSignalZs3Symbol::TWO),
-            3 => Ok(AdditionalSignalZs3Symbol::THREE),
-            4 => Ok(AdditionalSignalZs3Symbol::FOUR),
-            5 => Ok(AdditionalSignalZs3Symbol::FIVE),
-            6 => Ok(AdditionalSignalZs3Symbol::SIX),
-            7 => Ok(AdditionalSignalZs3Symbol::SEVEN),
-            8 => Ok(AdditionalSignalZs3Symbol::EIGHT),
-            9 => Ok(AdditionalSignalZs3Symbol::NINE),
-            10 => Ok(AdditionalSignalZs3Symbol::TEN),
-            11 => Ok(AdditionalSignalZs3Symbol::ELEVEN),
-            12 => Ok(AdditionalSignalZs3Symbol::TWELVE),
-            13 => Ok(AdditionalSignalZs3Symbol::THIRTEEN),
-            14 => Ok(AdditionalSignalZs3Symbol::FOURTEEN),
-            15 => Ok(AdditionalSignalZs3Symbol::FIFTEEN),
-            16 => Ok(AdditionalSignalZs3Symbol::SIXTEEN),
-            _ => Err(TrackElementError::InvalidAdditionalSignalState),
-        }
+            && self.system.is_none_or(|system| system.check(state).is_ok())
     }
 }
 
@@ -155,7 +168,42 @@ pub struct SignalState {
     zs3v: AdditionalSignalZs3Symbol,
 }
 
+impl MainSignalState {
+    pub fn is_stop(&self) -> bool {
+        matches!(
+            self,
+            MainSignalState::Hp0
+                | MainSignalState::Hp0PlusSh1
+                | MainSignalState::Hp0WithDrivingIndicator
+                | MainSignalState::Hp0Hv
+                | MainSignalState::Off
+        )
+    }
+
+    pub fn is_ks(&self) -> bool {
+        matches!(
+            self,
+            MainSignalState::Ks1
+                | MainSignalState::Ks1Flashing
+                | MainSignalState::Ks1FlashingWithAdditionalLight
+                | MainSignalState::Ks2
+                | MainSignalState::Ks2WithAdditionalLight
+        )
+    }
+}
+
 impl SignalState {
+    pub fn ks(speed: AdditionalSignalZs3Symbol, next: SignalState) -> Self {
+        let (main, zs3v) = if next.main().is_stop() {
+            (MainSignalState::Ks2, AdditionalSignalZs3Symbol::OFF)
+        } else if next.zs3() != AdditionalSignalZs3Symbol::OFF {
+            (MainSignalState::Ks1Flashing, next.zs3())
+        } else {
+            (MainSignalState::Ks1, AdditionalSignalZs3Symbol::OFF)
+        };
+        Self::new(main, AdditionalSignalState::Off, speed, zs3v)
+    }
+
     pub fn new(
         main: MainSignalState,
         additional: AdditionalSignalState,
@@ -213,6 +261,12 @@ pub struct Signal {
     supported_states: SupportedSignalStates,
     id: String,
     name: Option<String>,
+    reported: Option<SignalState>,
+    backend: Arc<dyn Backend<SignalState>>,
+    distant_signals: Vec<Arc<RwLock<DistantSignal>>>,
+    mounted_distant_signals: Vec<Arc<RwLock<DistantSignal>>>,
+    zs3_indicator: Option<Arc<RwLock<AdditionalSignalZs3>>>,
+    zs3v_indicator: Option<Arc<RwLock<AdditionalSignalZs3>>>,
 }
 
 impl Signal {
@@ -227,6 +281,12 @@ impl Signal {
             supported_states,
             id,
             name,
+            reported: None,
+            backend: SimulationBackend::new_arc(),
+            distant_signals: Vec::new(),
+            mounted_distant_signals: Vec::new(),
+            zs3_indicator: None,
+            zs3v_indicator: None,
         }
     }
 
@@ -239,8 +299,121 @@ impl Signal {
         Arc::new(RwLock::new(Self::new(state, supported_states, id, name)))
     }
 
+    pub fn set_backend(&mut self, backend: Arc<dyn Backend<SignalState>>) {
+        self.backend = backend;
+    }
+
+    pub fn mount_zs3(&mut self, indicator: Arc<RwLock<AdditionalSignalZs3>>) {
+        if indicator.read().unwrap().is_v() {
+            self.zs3v_indicator = Some(indicator);
+        } else {
+            self.zs3_indicator = Some(indicator);
+        }
+    }
+
+    pub fn zs3_indicator(&self) -> Option<&Arc<RwLock<AdditionalSignalZs3>>> {
+        self.zs3_indicator.as_ref()
+    }
+
+    pub fn zs3v_indicator(&self) -> Option<&Arc<RwLock<AdditionalSignalZs3>>> {
+        self.zs3v_indicator.as_ref()
+    }
+
+    fn set_indicators(&self, state: SignalState) -> Result<(), TrackElementError> {
+        if let Some(indicator) = &self.zs3_indicator {
+            indicator.write().unwrap().set_state(state.zs3)?;
+        }
+        if let Some(indicator) = &self.zs3v_indicator {
+            indicator.write().unwrap().set_state(state.zs3v)?;
+        }
+        Ok(())
+    }
+
+    fn supports_zs3(
+        indicator: &Option<Arc<RwLock<AdditionalSignalZs3>>>,
+        supported: &[AdditionalSignalZs3Symbol],
+        symbol: AdditionalSignalZs3Symbol,
+    ) -> bool {
+        match indicator {
+            Some(indicator) => indicator.read().unwrap().supports(symbol),
+            None => supported.contains(&symbol),
+        }
+    }
+
+    pub fn stop_state(&self) -> SignalState {
+        let supported = &self.supported_states;
+        match supported.system.map(|system| system.stop_aspect()) {
+            Some(stop) if supported.main.contains(&stop) => stop.into(),
+            _ => SignalState::default(),
+        }
+    }
+
     pub fn reset(&mut self) {
-        self.state = SignalState::default()
+        self.state = self.stop_state();
+        self.reported = None;
+        let _ = self.set_indicators(self.state);
+        // Replacing a signal must not be blocked by a failing backend
+        let _ = self.backend.command(&self.id, self.state);
+        self.notify_distant_signals();
+    }
+
+    pub fn report_state(&mut self, state: SignalState) {
+        self.reported = Some(state);
+        println!("Signal {} reports {:?}", self.id, state);
+        self.notify_distant_signals();
+    }
+
+    pub fn attach_distant_signal(&mut self, distant_signal: Arc<RwLock<DistantSignal>>) {
+        distant_signal
+            .write()
+            .unwrap()
+            .announce(self.state(), self.supported_states.signal_system());
+        self.distant_signals.push(distant_signal);
+    }
+
+    pub fn mount_distant_signal(&mut self, distant_signal: Arc<RwLock<DistantSignal>>) {
+        distant_signal
+            .write()
+            .unwrap()
+            .carrier_changed(self.state());
+        self.mounted_distant_signals.push(distant_signal);
+    }
+
+    pub fn distant_signals(&self) -> &[Arc<RwLock<DistantSignal>>] {
+        &self.distant_signals
+    }
+
+    fn notify_distant_signals(&self) {
+        let state = self.state();
+        let system = self.supported_states.signal_system();
+        for distant_signal in &self.distant_signals {
+            distant_signal.write().unwrap().announce(state, system);
+        }
+        for distant_signal in &self.mounted_distant_signals {
+            distant_signal.write().unwrap().carrier_changed(state);
+        }
+    }
+
+    pub fn supports(&self, state: SignalState) -> bool {
+        let supported = &self.supported_states;
+        supported.main.contains(&state.main)
+            && supported.additional.contains(&state.additional)
+            && Self::supports_zs3(&self.zs3_indicator, &supported.zs3, state.zs3)
+            && Self::supports_zs3(&self.zs3v_indicator, &supported.zs3v, state.zs3v)
+            && supported
+                .system
+                .is_none_or(|system| system.check(state).is_ok())
+    }
+
+    pub fn validate(&self, state: SignalState) -> Result<(), TrackElementError> {
+        if let Some(system) = self.supported_states.signal_system() {
+            system.validate(&self.id, state)?;
+        }
+        if self.supports(state) {
+            Ok(())
+        } else {
+            Err(TrackElementError::InvalidMainSignalState(state.main))
+        }
     }
 
     pub fn name(&self) -> &str {
@@ -256,16 +429,31 @@ impl TrackElement for Signal {
     }
 
     fn state(&self) -> Self::State {
+        let mut state = self
+            .reported
+            .or_else(|| self.backend.status(&self.id))
+            .unwrap_or(self.state);
+        if let Some(indicator) = &self.zs3_indicator {
+            state.zs3 = indicator.read().unwrap().state();
+        }
+        if let Some(indicator) = &self.zs3v_indicator {
+            state.zs3v = indicator.read().unwrap().state();
+        }
+        state
+    }
+
+    fn commanded_state(&self) -> Self::State {
         self.state
     }
 
     fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
-        if self.supported_states.is_signal_state_supported(new_state) {
-            self.state = new_state;
-            println!("Signal {} is now {:?}", self.id(), self.state);
-            Ok(())
-        } else {
-            Err(TrackElementError::InvalidMainSignalState(new_state.main))
-        }
+        self.validate(new_state)?;
+        self.backend.command(&self.id, new_state)?;
+        self.set_indicators(new_state)?;
+        self.state = new_state;
+        self.reported = None;
+        println!("Signal {} is now {:?}", self.id(), self.state);
+        self.notify_distant_signals();
+        Ok(())
     }
 }
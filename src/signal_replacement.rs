@@ -0,0 +1,104 @@
+use std::sync::{Arc, RwLock};
+
+use crate::signal::{MainSignalState, Signal};
+use crate::vacancy_section::VacancySectionState;
+use crate::{TrackElement, TrackElementError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MovementKind {
+    #[default]
+    Train,
+    Shunting,
+}
+
+impl MovementKind {
+    fn shows(&self, state: MainSignalState) -> bool {
+        let shunting = matches!(state, MainSignalState::Sh1 | MainSignalState::Hp0PlusSh1);
+        match self {
+            MovementKind::Shunting => shunting,
+            MovementKind::Train => {
+                !shunting
+                    && !matches!(
+                        state,
+                        MainSignalState::Hp0 | MainSignalState::Hp0Hv | MainSignalState::Off
+                    )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignalReplacementRule {
+    signal: Arc<RwLock<Signal>>,
+    sections: Vec<String>,
+    driveways: Vec<String>,
+    transitions: Vec<(VacancySectionState, VacancySectionState)>,
+    kind: MovementKind,
+}
+
+impl SignalReplacementRule {
+    pub fn new(signal: Arc<RwLock<Signal>>, sections: Vec<String>) -> Self {
+        Self {
+            signal,
+            sections,
+            driveways: Vec::new(),
+            transitions: Vec::new(),
+            kind: MovementKind::Train,
+        }
+    }
+
+    pub fn driveways(mut self, driveways: Vec<String>) -> Self {
+        self.driveways = driveways;
+        self
+    }
+
+    pub fn transitions(
+        mut self,
+        transitions: Vec<(VacancySectionState, VacancySectionState)>,
+    ) -> Self {
+        self.transitions = transitions;
+        self
+    }
+
+    pub fn shunting(mut self) -> Self {
+        self.kind = MovementKind::Shunting;
+        self
+    }
+
+    pub fn signal(&self) -> &Arc<RwLock<Signal>> {
+        &self.signal
+    }
+
+    pub fn kind(&self) -> MovementKind {
+        self.kind
+    }
+
+    pub fn applies_to(
+        &self,
+        section_id: &str,
+        from: VacancySectionState,
+        to: VacancySectionState,
+        set_driveways: &[String],
+    ) -> bool {
+        if !self.sections.iter().any(|s| s == section_id) {
+            return false;
+        }
+        if !self.driveways.is_empty() && !self.driveways.iter().any(|d| set_driveways.contains(d)) {
+            return false;
+        }
+        if self.transitions.is_empty() {
+            to.is_possibly_occupied()
+        } else {
+            self.transitions.contains(&(from, to))
+        }
+    }
+
+    pub fn apply(&self) -> Result<(), TrackElementError> {
+        let mut signal = self.signal.write().unwrap();
+        if self.kind.shows(signal.state().main()) {
+            let stop = signal.stop_state();
+            signal.set_state(stop)?;
+        }
+        Ok(())
+    }
+}
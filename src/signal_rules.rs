@@ -0,0 +1,89 @@
+use crate::signal::{
+    AdditionalSignalState, AdditionalSignalZs3Symbol, MainSignalState, SignalState,
+};
+use crate::TrackElementError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalSystem {
+    Ks,
+    Hv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalRule {
+    AspectOfOtherSignalSystem(MainSignalState),
+    Zs3RequiresProceedAspect,
+    Zs3vRequiresKs1Flashing,
+    Ks1FlashingRequiresZs3v,
+    Zs3vRequiresVr1OrVr2,
+    SubstituteSignalRequiresStopAspect(AdditionalSignalState),
+    AdditionalSignalRequiresProceedAspect(AdditionalSignalState),
+}
+
+impl SignalSystem {
+    pub fn stop_aspect(&self) -> MainSignalState {
+        match self {
+            SignalSystem::Ks => MainSignalState::Hp0,
+            SignalSystem::Hv => MainSignalState::Hp0Hv,
+        }
+    }
+
+    pub fn allows(&self, main: MainSignalState) -> bool {
+        use MainSignalState::*;
+        match self {
+            SignalSystem::Ks => !matches!(main, Hp0Hv | Hp1 | Hp2 | Vr0 | Vr1 | Vr2),
+            SignalSystem::Hv => !main.is_ks() && main != IdLight,
+        }
+    }
+
+    pub fn check(&self, state: SignalState) -> Result<(), SignalRule> {
+        let main = state.main();
+        if !self.allows(main) {
+            return Err(SignalRule::AspectOfOtherSignalSystem(main));
+        }
+        let proceed = !main.is_stop() && main != MainSignalState::Sh1;
+        if state.zs3() != AdditionalSignalZs3Symbol::OFF && !proceed {
+            return Err(SignalRule::Zs3RequiresProceedAspect);
+        }
+        let zs3v = state.zs3v() != AdditionalSignalZs3Symbol::OFF;
+        match self {
+            SignalSystem::Ks => {
+                let flashing = matches!(
+                    main,
+                    MainSignalState::Ks1Flashing | MainSignalState::Ks1FlashingWithAdditionalLight
+                );
+                if zs3v && !flashing {
+                    return Err(SignalRule::Zs3vRequiresKs1Flashing);
+                }
+                if flashing && !zs3v {
+                    return Err(SignalRule::Ks1FlashingRequiresZs3v);
+                }
+            }
+            SignalSystem::Hv => {
+                if zs3v && !matches!(main, MainSignalState::Vr1 | MainSignalState::Vr2) {
+                    return Err(SignalRule::Zs3vRequiresVr1OrVr2);
+                }
+            }
+        }
+        match state.additional() {
+            additional @ (AdditionalSignalState::Zs1
+            | AdditionalSignalState::Zs7
+            | AdditionalSignalState::Zs8)
+                if !main.is_stop() =>
+            {
+                Err(SignalRule::SubstituteSignalRequiresStopAspect(additional))
+            }
+            additional @ (AdditionalSignalState::Zs6 | AdditionalSignalState::Zs13) if !proceed => {
+                Err(SignalRule::AdditionalSignalRequiresProceedAspect(
+                    additional,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn validate(&self, id: &str, state: SignalState) -> Result<(), TrackElementError> {
+        self.check(state)
+            .map_err(|rule| TrackElementError::SignalRuleViolated(id.to_string(), rule))
+    }
+}
@@ -1,14 +1,49 @@
+use std::collections::BTreeMap;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use crate::signal::{MainSignalState, SupportedSignalStates};
+use crate::additional_signal::AdditionalSignalZs3;
+use crate::async_element::AsyncTrackElement;
+use crate::backend::{MockBackend, RemoteBackend, Transport};
+use crate::clock::ManualClock;
+
+use crate::signal::{
+    AdditionalSignalState, AdditionalSignalZs3Symbol, MainSignalState, SupportedSignalStates,
+};
+use crate::signal_rules::{SignalRule, SignalSystem};
 use crate::{
+    axle_counter::{
+        AxleCounter, AxleCounterHead, AxleCounterHeadState, AxleCounterReset, AxleDirection,
+    },
+    coupled_points::CoupledPoints,
+    crossing::{Crossing, CrossingState},
+    derailer::{Derailer, DerailerState},
+    distant_signal::{DistantSignal, DistantSignalKind},
+    double_slip::{DoubleSlip, DoubleSlipState},
     driveway::Driveway,
-    driveway::DrivewayState,
-    point::{Point, PointState},
+    driveway::{DrivewayDeviation, DrivewayManager, DrivewayState, DrivewayStatus, Overlap},
+    level_crossing::{LevelCrossing, LevelCrossingState},
+    point::{Lockable, Point, PointLock, PointState},
     signal::{Signal, SignalState},
-    TrackElement,
+    signal_replacement::SignalReplacementRule,
+    three_way_point::{ThreeWayPoint, ThreeWayPointState},
+    vacancy_section::{VacancySection, VacancySectionState},
+    TrackElement, TrackElementError,
 };
 
+fn ks_signal(id: &str) -> Arc<RwLock<Signal>> {
+    Signal::new_arc(
+        (MainSignalState::Hp0).into(),
+        SupportedSignalStates::default().main(&mut vec![
+            MainSignalState::Hp0,
+            MainSignalState::Ks1,
+            MainSignalState::Ks2,
+        ]),
+        id.to_string(),
+        None,
+    )
+}
+
 #[test]
 fn set_point() {
     let mut p = Point::new(PointState::Left, String::new());
@@ -20,8 +55,11 @@ fn set_point() {
 fn set_signal() {
     let mut s = Signal::new(
         (MainSignalState::Hp0).into(),
-        SupportedSignalStates::default()
-            .main(&mut vec![MainSignalState::Hp0, MainSignalState::Ks1]),
+        SupportedSignalStates::default().main(&mut vec![
+            MainSignalState::Hp0,
+            MainSignalState::Ks1,
+            MainSignalState::Ks2,
+        ]),
         "A".to_string(),
         None,
     );
@@ -34,13 +72,7 @@ fn set_signal() {
 fn set_basic_driveway() {
     let p1 = Arc::new(RwLock::new(Point::new(PointState::Left, "P1".to_string())));
     let p2 = Arc::new(RwLock::new(Point::new(PointState::Left, "P2".to_string())));
-    let s = Arc::new(RwLock::new(Signal::new(
-        SignalState::default(),
-        SupportedSignalStates::default()
-            .main(&mut vec![MainSignalState::Hp0, MainSignalState::Ks1]),
-        "S".to_string(),
-        None,
-    )));
+    let s = ks_signal("S");
 
     let ts = DrivewayState::new(
         vec![
@@ -64,34 +96,10 @@ fn set_basic_driveway() {
 
 #[test]
 fn set_conflicting_driveway() {
-    let s1 = Arc::new(RwLock::new(Signal::new(
-        (MainSignalState::Hp0).into(),
-        SupportedSignalStates::default()
-            .main(&mut vec![MainSignalState::Hp0, MainSignalState::Ks1]),
-        "A".to_string(),
-        None,
-    )));
-    let s2 = Arc::new(RwLock::new(Signal::new(
-        (MainSignalState::Hp0).into(),
-        SupportedSignalStates::default()
-            .main(&mut vec![MainSignalState::Hp0, MainSignalState::Ks1]),
-        "B".to_string(),
-        None,
-    )));
-    let s12 = Arc::new(RwLock::new(Signal::new(
-        (MainSignalState::Hp0).into(),
-        SupportedSignalStates::default()
-            .main(&mut vec![MainSignalState::Hp0, MainSignalState::Ks1]),
-        "C".to_string(),
-        None,
-    )));
-    let s22 = Arc::new(RwLock::new(Signal::new(
-        (MainSignalState::Hp0).into(),
-        SupportedSignalStates::default()
-            .main(&mut vec![MainSignalState::Hp0, MainSignalState::Ks1]),
-        "D".to_string(),
-        None,
-    )));
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let s12 = ks_signal("C");
+    let s22 = ks_signal("D");
 
     let dw1 = Arc::new(RwLock::new(Driveway::new(
         Vec::new(),
@@ -123,3 +131,2012 @@ fn set_conflicting_driveway() {
     dw1.write().unwrap().set_way().unwrap();
     assert!(dw2.set_way().is_err())
 }
+
+#[test]
+fn driveway_lifecycle() {
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let vs = VacancySection::new_arc("V1".to_string(), VacancySectionState::Free);
+
+    let ts = DrivewayState::new(
+        vec![(p1.clone(), PointState::Right)],
+        vec![(s1.clone(), (MainSignalState::Ks1).into())],
+        vec![(vs.clone(), VacancySectionState::Free)],
+    );
+    let mut dw = Driveway::new(Vec::new(), ts, s1.clone(), s2.clone());
+    assert_eq!(dw.status(), DrivewayStatus::Idle);
+    assert!(!dw.is_set());
+
+    dw.set_way().unwrap();
+    assert_eq!(dw.status(), DrivewayStatus::SignalCleared);
+    assert!(dw.is_set());
+
+    vs.write()
+        .unwrap()
+        .set_state(VacancySectionState::Occupied)
+        .unwrap();
+    dw.update().unwrap();
+    assert_eq!(dw.status(), DrivewayStatus::Occupied);
+    assert!(matches!(
+        dw.set_way(),
+        Err(TrackElementError::InvalidDrivewayTransition(
+            DrivewayStatus::Occupied,
+            DrivewayStatus::Requested
+        ))
+    ));
+}
+
+#[test]
+fn driveway_status_transitions() {
+    assert!(DrivewayStatus::Idle.can_transition_to(DrivewayStatus::Requested));
+    assert!(DrivewayStatus::Released.can_transition_to(DrivewayStatus::Requested));
+    assert!(DrivewayStatus::SignalCleared.can_transition_to(DrivewayStatus::Released));
+    assert!(!DrivewayStatus::Requested.can_transition_to(DrivewayStatus::SignalCleared));
+    assert!(!DrivewayStatus::Idle.can_transition_to(DrivewayStatus::Released));
+    assert!(DrivewayStatus::Requested.can_transition_to(DrivewayStatus::Idle));
+    assert!(!DrivewayStatus::SignalCleared.can_transition_to(DrivewayStatus::Idle));
+}
+
+#[test]
+fn cancel_driveway() {
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let dw1 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s1.clone(),
+        s2.clone(),
+    )));
+    let mut dw2 = Driveway::new(
+        vec![dw1.clone()],
+        DrivewayState::new(Vec::new(), Vec::new(), Vec::new()),
+        s2.clone(),
+        s1.clone(),
+    );
+
+    dw1.write().unwrap().set_way().unwrap();
+    assert!(dw2.set_way().is_err());
+
+    dw1.write().unwrap().cancel().unwrap();
+    assert_eq!(dw1.read().unwrap().status(), DrivewayStatus::Released);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+    dw2.set_way().unwrap();
+    assert!(dw1.write().unwrap().cancel().is_err());
+}
+
+#[test]
+fn cancel_driveway_delayed() {
+    let clock = ManualClock::new_arc();
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s1.clone(),
+        s2.clone(),
+    );
+    dw.set_clock(clock.clone());
+
+    dw.set_way().unwrap();
+    dw.cancel_delayed(Duration::from_secs(120)).unwrap();
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+    assert_eq!(dw.status(), DrivewayStatus::Locked);
+    assert!(dw.release_pending());
+
+    clock.advance(Duration::from_secs(60));
+    dw.update().unwrap();
+    assert_eq!(dw.status(), DrivewayStatus::Locked);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+
+    clock.advance(Duration::from_secs(60));
+    dw.update().unwrap();
+    assert_eq!(dw.status(), DrivewayStatus::Released);
+    assert!(!dw.release_pending());
+}
+
+#[test]
+fn set_driveway_with_unsupported_signal_state() {
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    let s1 = Signal::new_arc(
+        (MainSignalState::Hp0).into(),
+        SupportedSignalStates::default()
+            .main(&mut vec![MainSignalState::Hp0, MainSignalState::Ks1]),
+        "A".to_string(),
+        None,
+    );
+    let s2 = Signal::new_arc(
+        (MainSignalState::Hp0).into(),
+        SupportedSignalStates::default()
+            .main(&mut vec![MainSignalState::Hp0, MainSignalState::Ks1]),
+        "B".to_string(),
+        None,
+    );
+    let mut ts = DrivewayState::new(
+        vec![(p1.clone(), PointState::Right)],
+        vec![
+            (s1.clone(), (MainSignalState::Ks1).into()),
+            (s2.clone(), (MainSignalState::Ks2).into()),
+        ],
+        vec![],
+    );
+
+    assert!(matches!(
+        ts.set_state(),
+        Err(TrackElementError::UnsupportedSignalState(id, _)) if id == "B"
+    ));
+    assert_eq!(p1.read().unwrap().state(), PointState::Left);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+
+    let mut dw = Driveway::new(Vec::new(), ts, s1.clone(), s2.clone());
+    assert!(dw.set_way().is_err());
+    assert_eq!(dw.status(), DrivewayStatus::Idle);
+
+    // Next signal at stop requires Ks2, which A cannot show
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Right)],
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            vec![],
+        ),
+        s1.clone(),
+        s2.clone(),
+    );
+    assert!(matches!(
+        dw.set_way(),
+        Err(TrackElementError::UnsupportedSignalState(id, state))
+            if id == "A" && state.main() == MainSignalState::Ks2
+    ));
+    assert_eq!(dw.status(), DrivewayStatus::Idle);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+    assert_eq!(p1.read().unwrap().state(), PointState::Left);
+}
+
+#[test]
+fn set_driveway_requires_free_sections() {
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let v1 = VacancySection::new_arc("V1".to_string(), VacancySectionState::Free);
+    let v2 = VacancySection::new_arc("V2".to_string(), VacancySectionState::Occupied);
+    let v3 = VacancySection::new_arc("V3".to_string(), VacancySectionState::Disturbed);
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            vec![
+                (v1.clone(), VacancySectionState::Free),
+                (v2.clone(), VacancySectionState::Occupied),
+                (v3.clone(), VacancySectionState::Free),
+            ],
+        ),
+        s1.clone(),
+        s2.clone(),
+    );
+
+    match dw.set_way() {
+        Err(TrackElementError::VacancySectionsNotFree(sections)) => assert_eq!(
+            sections,
+            vec![
+                ("V2".to_string(), VacancySectionState::Occupied),
+                ("V3".to_string(), VacancySectionState::Disturbed)
+            ]
+        ),
+        other => panic!("unexpected result {other:?}"),
+    }
+    assert_eq!(v2.read().unwrap().state(), VacancySectionState::Occupied);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+
+    v2.write()
+        .unwrap()
+        .set_state(VacancySectionState::Free)
+        .unwrap();
+    assert!(matches!(
+        v3.write().unwrap().set_state(VacancySectionState::Free),
+        Err(TrackElementError::VacancySectionResetRequired(_))
+    ));
+    v3.write().unwrap().reset("operator").unwrap();
+    assert_eq!(
+        v3.read().unwrap().resets(),
+        &[("operator".to_string(), VacancySectionState::Disturbed)]
+    );
+    dw.set_way().unwrap();
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Ks2);
+}
+
+#[test]
+fn supervise_driveway() {
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let v1 = VacancySection::new_arc("V1".to_string(), VacancySectionState::Free);
+    let v2 = VacancySection::new_arc("V2".to_string(), VacancySectionState::Free);
+    let dw = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Right)],
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            vec![
+                (v1.clone(), VacancySectionState::Free),
+                (v2.clone(), VacancySectionState::Free),
+            ],
+        ),
+        s1.clone(),
+        s2.clone(),
+    )));
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw.clone());
+
+    manager.set_driveway("A", "B").unwrap();
+    assert!(manager.supervise().unwrap().is_empty());
+
+    v2.write()
+        .unwrap()
+        .set_state(VacancySectionState::Occupied)
+        .unwrap();
+    assert!(p1.write().unwrap().set_state(PointState::Left).is_err());
+    p1.write()
+        .unwrap()
+        .unlock(&PointLock::Driveway("A-B".to_string()));
+    p1.write().unwrap().set_state(PointState::Left).unwrap();
+    let deviations = manager.supervise().unwrap();
+    assert_eq!(
+        deviations.get("A-B").unwrap(),
+        &vec![
+            DrivewayDeviation::PointPosition("P1".to_string(), PointState::Left),
+            DrivewayDeviation::VacancySection("V2".to_string(), VacancySectionState::Occupied),
+        ]
+    );
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+    assert_eq!(dw.read().unwrap().status(), DrivewayStatus::Locked);
+
+    manager.update().unwrap();
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+}
+
+#[test]
+fn supervise_all_driveways() {
+    let (s1, s2, s3, s4) = (
+        ks_signal("A"),
+        ks_signal("B"),
+        ks_signal("C"),
+        ks_signal("D"),
+    );
+    let signals = MockBackend::new_arc();
+    s1.write().unwrap().set_backend(signals.clone());
+    let p2 = Point::new_arc(PointState::Left, "P2".to_string());
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s1.clone(),
+        s2.clone(),
+    ))));
+    manager.add(Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p2.clone(), PointState::Right)],
+            vec![(s3.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s3.clone(),
+        s4.clone(),
+    ))));
+    manager.set_driveway("A", "B").unwrap();
+    manager.set_driveway("C", "D").unwrap();
+
+    signals.set_failing(true);
+    s2.write()
+        .unwrap()
+        .set_state((MainSignalState::Ks1).into())
+        .unwrap();
+    p2.write().unwrap().lose_end_position();
+    match manager.supervise() {
+        Err(TrackElementError::SupervisionFailed(deviations, errors)) => {
+            assert_eq!(
+                deviations["C-D"],
+                vec![DrivewayDeviation::PointPosition(
+                    "P2".to_string(),
+                    PointState::NoEndPosition
+                )]
+            );
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].0, "A-B");
+        }
+        other => panic!("unexpected result {other:?}"),
+    }
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+    assert_eq!(s3.read().unwrap().state().main(), MainSignalState::Hp0);
+}
+
+#[test]
+fn sectional_release() {
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    let p2 = Point::new_arc(PointState::Left, "P2".to_string());
+    let (s1, s2, s3, s4) = (
+        ks_signal("A"),
+        ks_signal("B"),
+        ks_signal("C"),
+        ks_signal("D"),
+    );
+    let v1 = VacancySection::new_arc("V1".to_string(), VacancySectionState::Free);
+    let v2 = VacancySection::new_arc("V2".to_string(), VacancySectionState::Free);
+
+    let dw1 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![
+                (p1.clone(), PointState::Right),
+                (p2.clone(), PointState::Right),
+            ],
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            vec![
+                (v1.clone(), VacancySectionState::Free),
+                (v2.clone(), VacancySectionState::Free),
+            ],
+        ),
+        s1.clone(),
+        s2.clone(),
+    )));
+    dw1.write()
+        .unwrap()
+        .set_section_points("V1", vec!["P1".to_string()]);
+    dw1.write()
+        .unwrap()
+        .set_section_points("V2", vec!["P2".to_string()]);
+    let dw2 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Left)],
+            vec![(s3.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s3.clone(),
+        s4.clone(),
+    )));
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw1.clone());
+    manager.add(dw2.clone());
+    manager.update_conflicting_driveways();
+
+    manager.set_driveway("A", "B").unwrap();
+    assert!(manager.set_driveway("C", "D").is_err());
+
+    manager
+        .set_vacancy_section_state("V1", VacancySectionState::Occupied)
+        .unwrap();
+    assert_eq!(dw1.read().unwrap().status(), DrivewayStatus::Occupied);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+    assert!(!manager.supervise().unwrap().contains_key("A-B"));
+    manager
+        .set_vacancy_section_state("V2", VacancySectionState::Occupied)
+        .unwrap();
+    assert_eq!(dw1.read().unwrap().status(), DrivewayStatus::Occupied);
+    assert!(manager.set_driveway("C", "D").is_err());
+    assert!(matches!(
+        manager.release_driveway("A", "B"),
+        Err(TrackElementError::InvalidDrivewayTransition(
+            DrivewayStatus::Occupied,
+            DrivewayStatus::Released
+        ))
+    ));
+    assert!(p1.write().unwrap().set_state(PointState::Left).is_err());
+
+    manager
+        .set_vacancy_section_state("V1", VacancySectionState::Free)
+        .unwrap();
+    assert_eq!(dw1.read().unwrap().released_sections(), 1);
+    assert!(!dw1.read().unwrap().holds("P1"));
+    assert!(dw1.read().unwrap().holds("P2"));
+    assert!(!manager.is_allocated(&v1.read().unwrap()));
+    assert!(manager.is_allocated(&v2.read().unwrap()));
+    manager.set_driveway("C", "D").unwrap();
+    assert_eq!(p1.read().unwrap().state(), PointState::Left);
+
+    manager
+        .set_vacancy_section_state("V2", VacancySectionState::Free)
+        .unwrap();
+    assert_eq!(dw1.read().unwrap().status(), DrivewayStatus::Released);
+}
+
+#[test]
+fn driveway_overlap() {
+    let clock = ManualClock::new_arc();
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let v1 = VacancySection::new_arc("V1".to_string(), VacancySectionState::Free);
+    let v2 = VacancySection::new_arc("V2".to_string(), VacancySectionState::Occupied);
+    let v3 = VacancySection::new_arc("V3".to_string(), VacancySectionState::Free);
+
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            vec![(v1.clone(), VacancySectionState::Free)],
+        ),
+        s1.clone(),
+        s2.clone(),
+    );
+    dw.set_clock(clock.clone());
+    dw.set_overlaps(&mut vec![
+        Overlap::new(
+            "O1".to_string(),
+            vec![(p1.clone(), PointState::Left)],
+            vec![v2.clone()],
+            Duration::from_secs(30),
+        ),
+        Overlap::new(
+            "O2".to_string(),
+            vec![(p1.clone(), PointState::Right)],
+            vec![v3.clone()],
+            Duration::from_secs(30),
+        ),
+    ]);
+
+    assert!(matches!(
+        dw.set_way_with_overlap("O1"),
+        Err(TrackElementError::NoOverlapAvailable(_))
+    ));
+    dw.set_way().unwrap();
+    assert_eq!(dw.selected_overlap().unwrap().id(), "O2");
+    assert_eq!(p1.read().unwrap().state(), PointState::Right);
+    assert!(dw.holds("P1"));
+    assert!(dw.holds("V3"));
+
+    v1.write()
+        .unwrap()
+        .set_state(VacancySectionState::Occupied)
+        .unwrap();
+    dw.update().unwrap();
+    assert!(dw.selected_overlap().is_some());
+
+    clock.advance(Duration::from_secs(30));
+    dw.update().unwrap();
+    assert!(dw.selected_overlap().is_none());
+    assert!(!dw.holds("P1"));
+    assert_eq!(dw.status(), DrivewayStatus::Occupied);
+}
+
+#[test]
+fn overlap_conflicts() {
+    let (s1, s2, s3, s4) = (
+        ks_signal("A"),
+        ks_signal("B"),
+        ks_signal("C"),
+        ks_signal("D"),
+    );
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    let p9 = Point::new_arc(PointState::Left, "P9".to_string());
+    let dw1 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s1.clone(),
+        s2.clone(),
+    )));
+    dw1.write().unwrap().set_overlaps(&mut vec![
+        Overlap::new(
+            "O1".to_string(),
+            vec![(p9.clone(), PointState::Right)],
+            Vec::new(),
+            Duration::from_secs(30),
+        ),
+        Overlap::new(
+            "O2".to_string(),
+            vec![(p1.clone(), PointState::Left)],
+            Vec::new(),
+            Duration::from_secs(30),
+        ),
+    ]);
+    let dw2 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Right)],
+            vec![(s3.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s3.clone(),
+        s4.clone(),
+    )));
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw1.clone());
+    manager.add(dw2.clone());
+    manager.update_conflicting_driveways();
+
+    manager.set_driveway("C", "D").unwrap();
+    assert!(matches!(
+        manager.set_driveway_with_overlap("A", "B", "O2"),
+        Err(TrackElementError::NoOverlapAvailable(_))
+    ));
+    manager.set_driveway("A", "B").unwrap();
+    assert_eq!(dw1.read().unwrap().selected_overlap().unwrap().id(), "O1");
+
+    manager.release_driveway("A", "B").unwrap();
+    manager.release_driveway("C", "D").unwrap();
+    manager.set_driveway_with_overlap("A", "B", "O2").unwrap();
+    assert!(matches!(
+        manager.set_driveway("C", "D"),
+        Err(TrackElementError::HasConflictingDriveways)
+    ));
+}
+
+#[test]
+fn flank_protection() {
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    let p2 = Point::new_arc(PointState::Right, "P2".to_string());
+    let signal = |id: &str, state: MainSignalState| {
+        Signal::new_arc(
+            state.into(),
+            SupportedSignalStates::default().main(&mut vec![
+                MainSignalState::Hp0,
+                MainSignalState::Ks1,
+                MainSignalState::Ks2,
+            ]),
+            id.to_string(),
+            None,
+        )
+    };
+    let (s1, s2, s3, s4) = (
+        signal("A", MainSignalState::Hp0),
+        signal("B", MainSignalState::Hp0),
+        signal("C", MainSignalState::Hp0),
+        signal("D", MainSignalState::Hp0),
+    );
+    let (s5, s6) = (
+        signal("E", MainSignalState::Hp0),
+        signal("F", MainSignalState::Hp0),
+    );
+    let shunting = signal("X", MainSignalState::Ks1);
+
+    let dw1 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Right)],
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        )
+        .flank_points(vec![(p2.clone(), PointState::Left)])
+        .flank_signals(vec![(shunting.clone(), (MainSignalState::Hp0).into())]),
+        s1.clone(),
+        s2.clone(),
+    )));
+    let dw2 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p2.clone(), PointState::Right)],
+            vec![(s3.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s3.clone(),
+        s4.clone(),
+    )));
+    let dw3 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s5.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        )
+        .flank_points(vec![(p2.clone(), PointState::Left)]),
+        s5.clone(),
+        s6.clone(),
+    )));
+    let dw4 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(shunting.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        shunting.clone(),
+        s4.clone(),
+    )));
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw1.clone());
+    manager.add(dw2.clone());
+    manager.add(dw3.clone());
+    manager.add(dw4.clone());
+    manager.update_conflicting_driveways();
+
+    manager.set_driveway("A", "B").unwrap();
+    assert_eq!(p2.read().unwrap().state(), PointState::Left);
+    assert_eq!(
+        shunting.read().unwrap().state().main(),
+        MainSignalState::Hp0
+    );
+    assert!(dw1.read().unwrap().holds("P2"));
+    assert!(dw1.read().unwrap().holds("X"));
+    assert!(manager.set_driveway("C", "D").is_err());
+    assert!(manager.set_driveway("X", "D").is_err());
+    assert_eq!(
+        shunting.read().unwrap().state().main(),
+        MainSignalState::Hp0
+    );
+    manager.set_driveway("E", "F").unwrap();
+
+    shunting
+        .write()
+        .unwrap()
+        .set_state((MainSignalState::Ks1).into())
+        .unwrap();
+    let deviations = manager.supervise().unwrap();
+    assert_eq!(
+        deviations["A-B"],
+        vec![DrivewayDeviation::SignalState(
+            "X".to_string(),
+            (MainSignalState::Ks1).into()
+        )]
+    );
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+}
+
+#[test]
+fn point_locking() {
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    let p2 = Point::new_arc(PointState::Left, "P2".to_string());
+    let (s1, s2, s3, s4) = (
+        ks_signal("A"),
+        ks_signal("B"),
+        ks_signal("C"),
+        ks_signal("D"),
+    );
+
+    p2.write().unwrap().lock(PointLock::Operator);
+    assert!(matches!(
+        p2.write().unwrap().set_state(PointState::Right),
+        Err(TrackElementError::PointLocked(id)) if id == "P2"
+    ));
+    p2.write().unwrap().set_state(PointState::Left).unwrap();
+
+    let mut dw1 = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Right)],
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s1.clone(),
+        s2.clone(),
+    );
+    let mut dw2 = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![
+                (p1.clone(), PointState::Right),
+                (p2.clone(), PointState::Right),
+            ],
+            vec![(s3.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s3.clone(),
+        s4.clone(),
+    );
+
+    dw1.set_way().unwrap();
+    assert!(p1
+        .read()
+        .unwrap()
+        .locks()
+        .contains(&PointLock::Driveway("A-B".to_string())));
+    assert!(p1.write().unwrap().set_state(PointState::Left).is_err());
+
+    assert!(matches!(
+        dw2.set_way(),
+        Err(TrackElementError::PointLocked(id)) if id == "P2"
+    ));
+    assert_eq!(p1.read().unwrap().state(), PointState::Right);
+
+    dw1.cancel().unwrap();
+    assert!(!p1.read().unwrap().is_locked());
+    p1.write().unwrap().set_state(PointState::Left).unwrap();
+}
+
+#[test]
+fn point_movement() {
+    let clock = ManualClock::new_arc();
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    p1.write().unwrap().set_throw_time(Duration::from_secs(4));
+    p1.write().unwrap().set_clock(clock.clone());
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Right)],
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s1.clone(),
+        s2.clone(),
+    );
+
+    assert!(matches!(
+        p1.write().unwrap().set_state(PointState::Moving),
+        Err(TrackElementError::InvalidPointState(PointState::Moving))
+    ));
+
+    dw.set_way().unwrap();
+    assert_eq!(p1.read().unwrap().state(), PointState::Moving);
+    assert_eq!(dw.status(), DrivewayStatus::ElementsCommanded);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+
+    clock.advance(Duration::from_secs(4));
+    dw.update().unwrap();
+    assert_eq!(p1.read().unwrap().state(), PointState::Right);
+    assert_eq!(dw.status(), DrivewayStatus::SignalCleared);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Ks2);
+
+    p1.write().unwrap().lose_end_position();
+    assert_eq!(
+        dw.supervise().unwrap(),
+        vec![DrivewayDeviation::PointPosition(
+            "P1".to_string(),
+            PointState::NoEndPosition
+        )]
+    );
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+}
+
+#[test]
+fn driveway_with_complex_points() {
+    let dkw = DoubleSlip::new_arc(DoubleSlipState::LeftLeft, "W1".to_string());
+    let twp = ThreeWayPoint::new_arc(ThreeWayPointState::Straight, "W2".to_string());
+    let crossing = Crossing::new_arc(CrossingState::TrackA, "K1".to_string());
+    let (s1, s2, s3, s4) = (
+        ks_signal("A"),
+        ks_signal("B"),
+        ks_signal("C"),
+        ks_signal("D"),
+    );
+
+    let dw1 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        )
+        .double_slips(vec![(dkw.clone(), DoubleSlipState::RightLeft)])
+        .three_way_points(vec![(twp.clone(), ThreeWayPointState::Right)])
+        .crossings(vec![(crossing.clone(), CrossingState::TrackB)]),
+        s1.clone(),
+        s2.clone(),
+    )));
+    let dw2 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s3.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        )
+        .crossings(vec![(crossing.clone(), CrossingState::TrackA)]),
+        s3.clone(),
+        s4.clone(),
+    )));
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw1.clone());
+    manager.add(dw2.clone());
+    manager.update_conflicting_driveways();
+
+    manager.set_driveway("A", "B").unwrap();
+    assert_eq!(dkw.read().unwrap().state(), DoubleSlipState::RightLeft);
+    assert_eq!(twp.read().unwrap().state(), ThreeWayPointState::Right);
+    assert_eq!(crossing.read().unwrap().state(), CrossingState::TrackB);
+    assert!(dkw.read().unwrap().is_locked());
+    assert!(matches!(
+        dkw.write().unwrap().set_state(DoubleSlipState::LeftLeft),
+        Err(TrackElementError::PointLocked(id)) if id == "W1"
+    ));
+    assert!(matches!(
+        manager.set_driveway("C", "D"),
+        Err(TrackElementError::HasConflictingDriveways)
+    ));
+
+    manager.release_driveway("A", "B").unwrap();
+    assert!(!dkw.read().unwrap().is_locked());
+    manager.set_driveway("C", "D").unwrap();
+    assert_eq!(crossing.read().unwrap().state(), CrossingState::TrackA);
+}
+
+#[test]
+fn coupled_points() {
+    let p1 = Point::new_arc(PointState::Left, "W1a".to_string());
+    let p2 = Point::new_arc(PointState::Left, "W1b".to_string());
+    let coupled = CoupledPoints::new_arc("W1".to_string(), vec![p1.clone(), p2.clone()]);
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+
+    p2.write().unwrap().lock(PointLock::Operator);
+    assert!(coupled
+        .write()
+        .unwrap()
+        .set_state(PointState::Right)
+        .is_err());
+    assert_eq!(p1.read().unwrap().state(), PointState::Left);
+    p2.write().unwrap().unlock(&PointLock::Operator);
+
+    let s3 = ks_signal("C");
+    let s4 = ks_signal("D");
+
+    let dw = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        )
+        .coupled_points(vec![(coupled.clone(), PointState::Right)]),
+        s1.clone(),
+        s2.clone(),
+    )));
+    let dw2 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Left)],
+            vec![(s3.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s3.clone(),
+        s4.clone(),
+    )));
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw.clone());
+    manager.add(dw2.clone());
+    manager.update_conflicting_driveways();
+
+    manager.set_driveway("A", "B").unwrap();
+    assert_eq!(coupled.read().unwrap().state(), PointState::Right);
+    assert_eq!(p1.read().unwrap().state(), PointState::Right);
+    assert_eq!(p2.read().unwrap().state(), PointState::Right);
+    assert!(p1.write().unwrap().set_state(PointState::Left).is_err());
+    assert!(dw.read().unwrap().holds("W1a"));
+    assert!(matches!(
+        manager.set_driveway("C", "D"),
+        Err(TrackElementError::HasConflictingDriveways)
+    ));
+
+    p2.write().unwrap().lose_end_position();
+    assert_eq!(coupled.read().unwrap().state(), PointState::NoEndPosition);
+    assert_eq!(
+        dw.write().unwrap().supervise().unwrap(),
+        vec![DrivewayDeviation::CoupledPointsPosition(
+            "W1".to_string(),
+            PointState::NoEndPosition
+        )]
+    );
+}
+
+#[test]
+fn derailer_flank_protection() {
+    let derailer = Derailer::new_arc(DerailerState::OffRail, "Gs1".to_string());
+    let (s1, s2, s3, s4) = (
+        ks_signal("A"),
+        ks_signal("B"),
+        ks_signal("C"),
+        ks_signal("D"),
+    );
+
+    let dw1 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        )
+        .flank_derailers(vec![(derailer.clone(), DerailerState::OnRail)]),
+        s1.clone(),
+        s2.clone(),
+    )));
+    let dw2 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s3.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        )
+        .derailers(vec![(derailer.clone(), DerailerState::OffRail)]),
+        s3.clone(),
+        s4.clone(),
+    )));
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw1.clone());
+    manager.add(dw2.clone());
+    manager.update_conflicting_driveways();
+
+    manager.set_driveway("A", "B").unwrap();
+    assert_eq!(derailer.read().unwrap().state(), DerailerState::OnRail);
+    assert!(dw1.read().unwrap().holds("Gs1"));
+    assert!(derailer
+        .write()
+        .unwrap()
+        .set_state(DerailerState::OffRail)
+        .is_err());
+    assert!(manager.set_driveway("C", "D").is_err());
+
+    manager.release_driveway("A", "B").unwrap();
+    manager.set_driveway("C", "D").unwrap();
+    assert_eq!(derailer.read().unwrap().state(), DerailerState::OffRail);
+}
+
+#[test]
+fn level_crossing() {
+    let clock = ManualClock::new_arc();
+    let bue = LevelCrossing::new_arc("BUE1".to_string(), Duration::from_secs(30));
+    bue.write().unwrap().set_clock(clock.clone());
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let v1 = VacancySection::new_arc("V1".to_string(), VacancySectionState::Free);
+    let v2 = VacancySection::new_arc("V2".to_string(), VacancySectionState::Free);
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            vec![
+                (v1.clone(), VacancySectionState::Free),
+                (v2.clone(), VacancySectionState::Free),
+            ],
+        )
+        .level_crossings(vec![(bue.clone(), v1.clone())]),
+        s1.clone(),
+        s2.clone(),
+    );
+
+    assert!(matches!(
+        bue.write().unwrap().set_state(LevelCrossingState::Closing),
+        Err(TrackElementError::InvalidLevelCrossingState(
+            LevelCrossingState::Closing
+        ))
+    ));
+
+    dw.set_way().unwrap();
+    assert_eq!(bue.read().unwrap().state(), LevelCrossingState::Closing);
+    assert_eq!(dw.status(), DrivewayStatus::ElementsCommanded);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+
+    clock.advance(Duration::from_secs(30));
+    dw.update().unwrap();
+    assert_eq!(bue.read().unwrap().state(), LevelCrossingState::Closed);
+    assert_eq!(dw.status(), DrivewayStatus::SignalCleared);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Ks2);
+
+    v1.write()
+        .unwrap()
+        .set_state(VacancySectionState::Occupied)
+        .unwrap();
+    dw.update().unwrap();
+    assert_eq!(bue.read().unwrap().state(), LevelCrossingState::Closed);
+    v2.write()
+        .unwrap()
+        .set_state(VacancySectionState::Occupied)
+        .unwrap();
+    v1.write()
+        .unwrap()
+        .set_state(VacancySectionState::Free)
+        .unwrap();
+    dw.update().unwrap();
+    assert_eq!(bue.read().unwrap().state(), LevelCrossingState::Open);
+    assert_eq!(dw.status(), DrivewayStatus::Occupied);
+    assert!(dw.supervise().unwrap().is_empty());
+
+    v2.write()
+        .unwrap()
+        .set_state(VacancySectionState::Free)
+        .unwrap();
+    dw.update().unwrap();
+    assert_eq!(dw.status(), DrivewayStatus::Released);
+
+    bue.write().unwrap().report_fault();
+    assert!(matches!(
+        dw.set_way(),
+        Err(TrackElementError::LevelCrossingFault(_))
+    ));
+    bue.write().unwrap().repair();
+    dw.set_way().unwrap();
+    dw.cancel().unwrap();
+    assert_eq!(bue.read().unwrap().state(), LevelCrossingState::Open);
+
+    // A crossing shared by two driveways only opens once both have released it
+    let v3 = VacancySection::new_arc("V3".to_string(), VacancySectionState::Free);
+    let mut dw2 = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s2.clone(), (MainSignalState::Ks1).into())],
+            vec![(v3.clone(), VacancySectionState::Free)],
+        )
+        .level_crossings(vec![(bue.clone(), v3.clone())]),
+        s2.clone(),
+        s1.clone(),
+    );
+    dw.set_way().unwrap();
+    dw2.set_way().unwrap();
+    clock.advance(Duration::from_secs(30));
+    dw.update().unwrap();
+    dw2.update().unwrap();
+    assert_eq!(dw2.status(), DrivewayStatus::SignalCleared);
+
+    v1.write()
+        .unwrap()
+        .set_state(VacancySectionState::Occupied)
+        .unwrap();
+    dw.update().unwrap();
+    v1.write()
+        .unwrap()
+        .set_state(VacancySectionState::Free)
+        .unwrap();
+    dw.update().unwrap();
+    assert_eq!(bue.read().unwrap().state(), LevelCrossingState::Closed);
+    dw2.update().unwrap();
+    assert!(dw2.supervise().unwrap().is_empty());
+
+    dw2.cancel().unwrap();
+    assert_eq!(bue.read().unwrap().state(), LevelCrossingState::Open);
+}
+
+#[test]
+fn axle_counter() {
+    let s1 = Signal::new_arc(
+        (MainSignalState::Ks1).into(),
+        SupportedSignalStates::default().main(&mut vec![
+            MainSignalState::Hp0,
+            MainSignalState::Ks1,
+            MainSignalState::Ks2,
+        ]),
+        "A".to_string(),
+        None,
+    );
+    let v1 = VacancySection::new_arc("V1".to_string(), VacancySectionState::Free);
+    let h1 = AxleCounterHead::new_arc("Z1".to_string());
+    let h2 = AxleCounterHead::new_arc("Z2".to_string());
+    let counter = AxleCounter::new_arc("AZ1".to_string(), v1.clone(), vec![h1.clone(), h2]);
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add_axle_counter(counter.clone());
+    manager.set_replacement_rules(vec![SignalReplacementRule::new(
+        s1.clone(),
+        vec!["V1".to_string()],
+    )]);
+
+    assert!(matches!(
+        manager.count_axle("AZ1", "Z3", AxleDirection::In),
+        Err(TrackElementError::AxleCounterHeadDoesNotExist(_))
+    ));
+    assert!(matches!(
+        manager.count_axle("AZ2", "Z1", AxleDirection::In),
+        Err(TrackElementError::AxleCounterDoesNotExist(_))
+    ));
+
+    for _ in 0..4 {
+        manager.count_axle("AZ1", "Z1", AxleDirection::In).unwrap();
+    }
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Occupied);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+    for _ in 0..4 {
+        manager.count_axle("AZ1", "Z2", AxleDirection::Out).unwrap();
+    }
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Free);
+
+    s1.write()
+        .unwrap()
+        .set_state((MainSignalState::Ks1).into())
+        .unwrap();
+    manager.count_axle("AZ1", "Z2", AxleDirection::Out).unwrap();
+    assert_eq!(counter.read().unwrap().axles(), -1);
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Disturbed);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+
+    manager
+        .reset_axle_counter("AZ1", AxleCounterReset::Preparatory, "op")
+        .unwrap();
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Occupied);
+    manager.count_axle("AZ1", "Z1", AxleDirection::In).unwrap();
+    manager.count_axle("AZ1", "Z2", AxleDirection::Out).unwrap();
+    assert_eq!(counter.read().unwrap().pending_reset(), None);
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Free);
+
+    h1.write()
+        .unwrap()
+        .set_state(AxleCounterHeadState::Faulty)
+        .unwrap();
+    manager.count_axle("AZ1", "Z1", AxleDirection::In).unwrap();
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Disturbed);
+    h1.write()
+        .unwrap()
+        .set_state(AxleCounterHeadState::Operational)
+        .unwrap();
+
+    manager
+        .reset_axle_counter("AZ1", AxleCounterReset::Direct, "op")
+        .unwrap();
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Free);
+    assert_eq!(
+        counter.read().unwrap().pending_reset(),
+        Some(AxleCounterReset::Direct)
+    );
+    manager.count_axle("AZ1", "Z1", AxleDirection::In).unwrap();
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Occupied);
+    manager.count_axle("AZ1", "Z2", AxleDirection::Out).unwrap();
+    assert_eq!(counter.read().unwrap().pending_reset(), None);
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Free);
+}
+
+#[test]
+fn disturbed_vacancy_section() {
+    let s1 = Signal::new_arc(
+        (MainSignalState::Ks1).into(),
+        SupportedSignalStates::default().main(&mut vec![
+            MainSignalState::Hp0,
+            MainSignalState::Ks1,
+            MainSignalState::Ks2,
+        ]),
+        "A".to_string(),
+        None,
+    );
+    let s2 = ks_signal("B");
+    let v1 = VacancySection::new_arc("V1".to_string(), VacancySectionState::Free);
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s2.clone(), (MainSignalState::Ks1).into())],
+            vec![(v1.clone(), VacancySectionState::Free)],
+        ),
+        s2.clone(),
+        s1.clone(),
+    ))));
+    manager.set_replacement_rules(vec![SignalReplacementRule::new(
+        s1.clone(),
+        vec!["V1".to_string()],
+    )]);
+
+    manager
+        .set_vacancy_section_state("V1", VacancySectionState::CommunicationError)
+        .unwrap();
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+    manager
+        .set_vacancy_section_state("V1", VacancySectionState::Disturbed)
+        .unwrap();
+    assert!(matches!(
+        manager.set_vacancy_section_state("V1", VacancySectionState::Free),
+        Err(TrackElementError::VacancySectionResetRequired(_))
+    ));
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Disturbed);
+
+    v1.write()
+        .unwrap()
+        .set_state(VacancySectionState::Occupied)
+        .unwrap();
+    assert!(matches!(
+        v1.write().unwrap().set_state(VacancySectionState::Free),
+        Err(TrackElementError::VacancySectionResetRequired(_))
+    ));
+    assert!(v1.read().unwrap().reset_required());
+
+    manager.reset_vacancy_section("V1", "operator").unwrap();
+    assert_eq!(v1.read().unwrap().state(), VacancySectionState::Free);
+    assert_eq!(v1.read().unwrap().resets().len(), 1);
+    assert!(!v1.read().unwrap().reset_required());
+}
+
+#[test]
+fn signal_replacement_rules() {
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let s3 = Signal::new_arc(
+        (MainSignalState::Sh1).into(),
+        SupportedSignalStates::default()
+            .main(&mut vec![MainSignalState::Hp0, MainSignalState::Sh1]),
+        "C".to_string(),
+        None,
+    );
+    let s4 = Signal::new_arc(
+        (MainSignalState::Ks1).into(),
+        SupportedSignalStates::default().main(&mut vec![
+            MainSignalState::Hp0,
+            MainSignalState::Ks1,
+            MainSignalState::Ks2,
+        ]),
+        "D".to_string(),
+        None,
+    );
+    let v1 = VacancySection::new_arc("V1".to_string(), VacancySectionState::Free);
+    let dw = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            vec![(v1.clone(), VacancySectionState::Free)],
+        ),
+        s1.clone(),
+        s2.clone(),
+    )));
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw.clone());
+    manager.set_replacement_rules(vec![
+        SignalReplacementRule::new(s1.clone(), vec!["V1".to_string()])
+            .driveways(vec!["A-B".to_string()]),
+        SignalReplacementRule::new(s3.clone(), vec!["V1".to_string()]).shunting(),
+        SignalReplacementRule::new(s4.clone(), vec!["V1".to_string()]).transitions(vec![(
+            VacancySectionState::Occupied,
+            VacancySectionState::Disturbed,
+        )]),
+    ]);
+
+    manager.set_driveway("A", "B").unwrap();
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Ks2);
+    manager
+        .set_vacancy_section_state("V1", VacancySectionState::Occupied)
+        .unwrap();
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+    assert_eq!(s3.read().unwrap().state().main(), MainSignalState::Hp0);
+    assert_eq!(s4.read().unwrap().state().main(), MainSignalState::Ks1);
+
+    manager
+        .set_vacancy_section_state("V1", VacancySectionState::Disturbed)
+        .unwrap();
+    assert_eq!(s4.read().unwrap().state().main(), MainSignalState::Hp0);
+    manager.reset_vacancy_section("V1", "operator").unwrap();
+    assert_eq!(dw.read().unwrap().status(), DrivewayStatus::Released);
+
+    s1.write()
+        .unwrap()
+        .set_state((MainSignalState::Ks1).into())
+        .unwrap();
+    manager
+        .set_vacancy_section_state("V1", VacancySectionState::Occupied)
+        .unwrap();
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Ks1);
+}
+
+#[test]
+fn commanded_and_reported_state() {
+    let clock = ManualClock::new_arc();
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    p1.write().unwrap().set_throw_time(Duration::from_secs(4));
+    p1.write().unwrap().set_clock(clock.clone());
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Right)],
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s1.clone(),
+        s2.clone(),
+    );
+
+    dw.set_way().unwrap();
+    {
+        let p1 = p1.read().unwrap();
+        assert_eq!(p1.commanded_state(), PointState::Right);
+        assert_eq!(p1.reported_state(), PointState::Moving);
+        assert!(p1.has_mismatch());
+    }
+    assert_eq!(dw.state().points()[0].1, PointState::Moving);
+
+    clock.advance(Duration::from_secs(4));
+    dw.update().unwrap();
+    assert!(!p1.read().unwrap().has_mismatch());
+    assert_eq!(dw.status(), DrivewayStatus::SignalCleared);
+
+    s1.write()
+        .unwrap()
+        .report_state((MainSignalState::Hp0).into());
+    assert_eq!(
+        s1.read().unwrap().commanded_state().main(),
+        MainSignalState::Ks2
+    );
+    assert!(s1.read().unwrap().has_mismatch());
+    assert_eq!(
+        dw.supervise().unwrap(),
+        vec![DrivewayDeviation::SignalState(
+            "A".to_string(),
+            (MainSignalState::Hp0).into()
+        )]
+    );
+    assert!(!s1.read().unwrap().has_mismatch());
+}
+
+#[test]
+fn elements_report_state() {
+    let ds1 = DoubleSlip::new_arc(DoubleSlipState::LeftLeft, "DS1".to_string());
+    let d1 = Derailer::new_arc(DerailerState::OnRail, "D1".to_string());
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        )
+        .double_slips(vec![(ds1.clone(), DoubleSlipState::RightRight)])
+        .derailers(vec![(d1.clone(), DerailerState::OffRail)]),
+        s1.clone(),
+        s2.clone(),
+    );
+    dw.set_way().unwrap();
+    assert_eq!(dw.status(), DrivewayStatus::SignalCleared);
+
+    ds1.write()
+        .unwrap()
+        .report_state(DoubleSlipState::RightLeft);
+    d1.write().unwrap().report_state(DerailerState::OnRail);
+    assert!(ds1.read().unwrap().has_mismatch());
+    assert_eq!(
+        ds1.read().unwrap().commanded_state(),
+        DoubleSlipState::RightRight
+    );
+    assert!(d1.read().unwrap().has_mismatch());
+    let deviations = dw.supervise().unwrap();
+    assert!(deviations.contains(&DrivewayDeviation::DoubleSlipPosition(
+        "DS1".to_string(),
+        DoubleSlipState::RightLeft
+    )));
+    assert!(deviations.contains(&DrivewayDeviation::DerailerPosition(
+        "D1".to_string(),
+        DerailerState::OnRail
+    )));
+
+    let vs1 = VacancySection::new_arc("VS1".to_string(), VacancySectionState::Free);
+    vs1.write()
+        .unwrap()
+        .report_state(VacancySectionState::Disturbed);
+    assert!(vs1.read().unwrap().has_mismatch());
+    assert!(vs1.read().unwrap().reset_required());
+    vs1.write().unwrap().reset("operator").unwrap();
+    assert!(!vs1.read().unwrap().has_mismatch());
+
+    let head = AxleCounterHead::new_arc("AC1".to_string());
+    head.write()
+        .unwrap()
+        .report_state(AxleCounterHeadState::Faulty);
+    assert!(head.read().unwrap().has_mismatch());
+    head.write()
+        .unwrap()
+        .set_state(AxleCounterHeadState::Operational)
+        .unwrap();
+    assert!(!head.read().unwrap().has_mismatch());
+}
+
+#[derive(Debug, Default)]
+struct LoopbackTransport {
+    messages: RwLock<Vec<serde_json::Value>>,
+}
+
+impl Transport for LoopbackTransport {
+    fn send(&self, message: serde_json::Value) -> Result<(), TrackElementError> {
+        self.messages.write().unwrap().push(message);
+        Ok(())
+    }
+
+    fn status(&self, id: &str) -> Option<serde_json::Value> {
+        self.messages
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|m| m["id"] == id)
+            .map(|m| m["state"].clone())
+    }
+}
+
+#[test]
+fn element_backends() {
+    let points = MockBackend::new_arc();
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    p1.write().unwrap().set_backend(points.clone());
+    let transport = Arc::new(LoopbackTransport::default());
+    let s1 = ks_signal("A");
+    s1.write()
+        .unwrap()
+        .set_backend(RemoteBackend::new_arc(transport.clone()));
+    let s2 = ks_signal("B");
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Right)],
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s1.clone(),
+        s2.clone(),
+    );
+
+    points.set_failing(true);
+    assert!(matches!(
+        dw.set_way(),
+        Err(TrackElementError::ElementFailed(_, e)) if matches!(*e, TrackElementError::BackendFailed(_))
+    ));
+    assert_eq!(p1.read().unwrap().commanded_state(), PointState::Left);
+
+    assert_eq!(dw.status(), DrivewayStatus::Idle);
+    points.set_failing(false);
+    dw.set_way().unwrap();
+    assert_eq!(
+        points.commands(),
+        vec![("P1".to_string(), PointState::Right)]
+    );
+    assert_eq!(dw.status(), DrivewayStatus::SignalCleared);
+    assert_eq!(
+        transport.messages.read().unwrap().last().unwrap()["state"]["main"],
+        MainSignalState::Ks2 as u8
+    );
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Ks2);
+
+    points.set_status("P1", PointState::NoEndPosition);
+    assert!(p1.read().unwrap().has_mismatch());
+    assert_eq!(
+        dw.supervise().unwrap(),
+        vec![DrivewayDeviation::PointPosition(
+            "P1".to_string(),
+            PointState::NoEndPosition
+        )]
+    );
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn async_confirmation() {
+    let points = MockBackend::new_arc();
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    p1.write().unwrap().set_backend(points.clone());
+    AsyncTrackElement::set_state(&p1, PointState::Right)
+        .await
+        .unwrap();
+
+    points.set_status("P1", PointState::Right);
+    match AsyncTrackElement::set_state(&p1, PointState::Left).await {
+        Err(TrackElementError::ConfirmationTimeout(id)) => assert_eq!(id, "P1"),
+        other => panic!("unexpected result {other:?}"),
+    }
+
+    let p2 = Point::new_arc(PointState::Left, "P2".to_string());
+    let s1 = ks_signal("A");
+    let s2 = ks_signal("B");
+    let dw = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p2.clone(), PointState::Right)],
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s1.clone(),
+        s2.clone(),
+    )));
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw.clone());
+    let manager = Arc::new(manager);
+
+    let handle = tokio::spawn({
+        let manager = manager.clone();
+        async move { manager.set_driveway_async("A", "B").await }
+    });
+    handle.await.unwrap().unwrap();
+    assert_eq!(dw.read().unwrap().status(), DrivewayStatus::SignalCleared);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Ks2);
+
+    manager.release_driveway("A", "B").unwrap();
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Left)],
+            vec![(s1.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        s1.clone(),
+        s2.clone(),
+    );
+    p1.write().unwrap().set_state(PointState::Right).unwrap();
+    assert!(matches!(
+        dw.set_way_async().await,
+        Err(TrackElementError::ConfirmationTimeout(_))
+    ));
+    assert_eq!(dw.status(), DrivewayStatus::Released);
+    assert_eq!(s1.read().unwrap().state().main(), MainSignalState::Hp0);
+}
+
+#[test]
+fn ks_aspect_chaining() {
+    let zs3_signal = |id: &str| {
+        Signal::new_arc(
+            (MainSignalState::Hp0).into(),
+            SupportedSignalStates::default()
+                .main(&mut vec![
+                    MainSignalState::Hp0,
+                    MainSignalState::Ks1,
+                    MainSignalState::Ks1Flashing,
+                    MainSignalState::Ks2,
+                ])
+                .zs3(&mut vec![AdditionalSignalZs3Symbol::SIX])
+                .zs3v(&mut vec![AdditionalSignalZs3Symbol::SIX]),
+            id.to_string(),
+            None,
+        )
+    };
+    let a = zs3_signal("A");
+    let b = zs3_signal("B");
+    let c = zs3_signal("C");
+    let dw1 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(a.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        a.clone(),
+        b.clone(),
+    )));
+    let dw2 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(b.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        b.clone(),
+        c.clone(),
+    )));
+    dw2.write()
+        .unwrap()
+        .set_speed(AdditionalSignalZs3Symbol::SIX);
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw1.clone());
+    manager.add(dw2.clone());
+
+    manager.set_driveway("A", "B").unwrap();
+    assert_eq!(a.read().unwrap().state().main(), MainSignalState::Ks2);
+
+    manager.set_driveway("B", "C").unwrap();
+    let b_state = b.read().unwrap().state();
+    assert_eq!(b_state.main(), MainSignalState::Ks2);
+    assert_eq!(b_state.zs3(), AdditionalSignalZs3Symbol::SIX);
+    let a_state = a.read().unwrap().state();
+    assert_eq!(a_state.main(), MainSignalState::Ks1Flashing);
+    assert_eq!(a_state.zs3v(), AdditionalSignalZs3Symbol::SIX);
+    assert!(manager.supervise().unwrap().is_empty());
+
+    manager.release_driveway("B", "C").unwrap();
+    assert_eq!(a.read().unwrap().state().main(), MainSignalState::Ks2);
+
+    // D cannot announce the speed restriction at B, so it must not stay cleared
+    let d = ks_signal("D");
+    let dw3 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(d.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        d.clone(),
+        b.clone(),
+    )));
+    manager.add(dw3.clone());
+    manager.set_driveway("D", "B").unwrap();
+    assert_eq!(d.read().unwrap().state().main(), MainSignalState::Ks2);
+    let Err(TrackElementError::DrivewaysFailed(errors)) = manager.set_driveway("B", "C") else {
+        panic!("D-B can no longer show Ks2");
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "D-B");
+    assert!(matches!(
+        errors[0].1,
+        TrackElementError::UnsupportedSignalState(_, _)
+    ));
+    assert_eq!(d.read().unwrap().state().main(), MainSignalState::Hp0);
+    assert_eq!(dw3.read().unwrap().status(), DrivewayStatus::Locked);
+}
+
+#[test]
+fn distant_signals() {
+    let hv_signal = |id: &str| {
+        Signal::new_arc(
+            (MainSignalState::Hp0).into(),
+            SupportedSignalStates::default()
+                .main(&mut vec![
+                    MainSignalState::Hp0,
+                    MainSignalState::Hp0Hv,
+                    MainSignalState::Hp1,
+                    MainSignalState::Hp2,
+                ])
+                .system(SignalSystem::Hv),
+            id.to_string(),
+            None,
+        )
+    };
+    let a = hv_signal("A");
+    let b = hv_signal("B");
+    let vb = DistantSignal::new_arc("VB".to_string(), DistantSignalKind::Distant);
+    let av = DistantSignal::new_arc("AV".to_string(), DistantSignalKind::Distant);
+    b.write().unwrap().attach_distant_signal(vb.clone());
+    b.write().unwrap().attach_distant_signal(av.clone());
+    a.write().unwrap().mount_distant_signal(av.clone());
+    assert_eq!(vb.read().unwrap().state().main(), MainSignalState::Vr0);
+    assert_eq!(av.read().unwrap().state().main(), MainSignalState::Off);
+
+    a.write()
+        .unwrap()
+        .set_state((MainSignalState::Hp1).into())
+        .unwrap();
+    assert_eq!(av.read().unwrap().state().main(), MainSignalState::Vr0);
+    b.write()
+        .unwrap()
+        .set_state((MainSignalState::Hp2).into())
+        .unwrap();
+    assert_eq!(vb.read().unwrap().state().main(), MainSignalState::Vr2);
+    assert_eq!(av.read().unwrap().state().main(), MainSignalState::Vr2);
+    a.write().unwrap().reset();
+    assert_eq!(a.read().unwrap().state().main(), MainSignalState::Hp0Hv);
+    assert_eq!(av.read().unwrap().state().main(), MainSignalState::Off);
+    b.write().unwrap().reset();
+    assert_eq!(vb.read().unwrap().state().main(), MainSignalState::Vr0);
+
+    let k = ks_signal("K");
+    let kd = DistantSignal::new_arc("KD".to_string(), DistantSignalKind::Distant);
+    let kr = DistantSignal::new_arc("KR".to_string(), DistantSignalKind::Repeater);
+    k.write().unwrap().attach_distant_signal(kd.clone());
+    k.write().unwrap().attach_distant_signal(kr.clone());
+    assert_eq!(kd.read().unwrap().state().main(), MainSignalState::Ks2);
+    assert_eq!(
+        kr.read().unwrap().state().main(),
+        MainSignalState::Ks2WithAdditionalLight
+    );
+    k.write()
+        .unwrap()
+        .set_state((MainSignalState::Ks1).into())
+        .unwrap();
+    assert_eq!(kd.read().unwrap().state().main(), MainSignalState::Ks1);
+    assert_eq!(kr.read().unwrap().state().main(), MainSignalState::Ks1);
+}
+
+#[test]
+fn signal_rules() {
+    let s1 = Signal::new_arc(
+        (MainSignalState::Hp0).into(),
+        SupportedSignalStates::default()
+            .main(&mut vec![
+                MainSignalState::Hp0,
+                MainSignalState::Ks1,
+                MainSignalState::Ks1Flashing,
+                MainSignalState::Hp1,
+            ])
+            .additional(&mut vec![
+                AdditionalSignalState::Zs1,
+                AdditionalSignalState::Zs6,
+            ])
+            .zs3(&mut vec![AdditionalSignalZs3Symbol::SIX])
+            .zs3v(&mut vec![AdditionalSignalZs3Symbol::SIX])
+            .system(SignalSystem::Ks),
+        "A".to_string(),
+        None,
+    );
+    let state = |main, additional, zs3, zs3v| SignalState::new(main, additional, zs3, zs3v);
+    let off = AdditionalSignalZs3Symbol::OFF;
+    let six = AdditionalSignalZs3Symbol::SIX;
+    let violations = [
+        (
+            state(MainSignalState::Hp1, AdditionalSignalState::Off, off, off),
+            SignalRule::AspectOfOtherSignalSystem(MainSignalState::Hp1),
+        ),
+        (
+            state(MainSignalState::Ks1, AdditionalSignalState::Off, off, six),
+            SignalRule::Zs3vRequiresKs1Flashing,
+        ),
+        (
+            state(
+                MainSignalState::Ks1Flashing,
+                AdditionalSignalState::Off,
+                off,
+                off,
+            ),
+            SignalRule::Ks1FlashingRequiresZs3v,
+        ),
+        (
+            state(MainSignalState::Hp0, AdditionalSignalState::Off, six, off),
+            SignalRule::Zs3RequiresProceedAspect,
+        ),
+        (
+            state(MainSignalState::Ks1, AdditionalSignalState::Zs1, off, off),
+            SignalRule::SubstituteSignalRequiresStopAspect(AdditionalSignalState::Zs1),
+        ),
+        (
+            state(MainSignalState::Hp0, AdditionalSignalState::Zs6, off, off),
+            SignalRule::AdditionalSignalRequiresProceedAspect(AdditionalSignalState::Zs6),
+        ),
+    ];
+    for (illegal, rule) in violations {
+        assert!(!s1.read().unwrap().supports(illegal));
+        match s1.write().unwrap().set_state(illegal) {
+            Err(TrackElementError::SignalRuleViolated(id, violated)) => {
+                assert_eq!(id, "A");
+                assert_eq!(violated, rule);
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+    s1.write()
+        .unwrap()
+        .set_state(state(
+            MainSignalState::Ks1Flashing,
+            AdditionalSignalState::Zs6,
+            six,
+            six,
+        ))
+        .unwrap();
+    s1.write()
+        .unwrap()
+        .set_state(state(
+            MainSignalState::Hp0,
+            AdditionalSignalState::Zs1,
+            off,
+            off,
+        ))
+        .unwrap();
+
+    assert_eq!(
+        SignalSystem::Hv.check((MainSignalState::Ks1).into()),
+        Err(SignalRule::AspectOfOtherSignalSystem(MainSignalState::Ks1))
+    );
+    assert_eq!(
+        SignalSystem::Hv.check(state(
+            MainSignalState::Hp1,
+            AdditionalSignalState::Off,
+            off,
+            six
+        )),
+        Err(SignalRule::Zs3vRequiresVr1OrVr2)
+    );
+    assert!(SignalSystem::Hv
+        .check(state(
+            MainSignalState::Vr1,
+            AdditionalSignalState::Off,
+            off,
+            six
+        ))
+        .is_ok());
+}
+
+#[test]
+fn zs3_indicators() {
+    let a = ks_signal("A");
+    let b = ks_signal("B");
+    let zs3 = AdditionalSignalZs3::new_arc(
+        false,
+        vec![
+            AdditionalSignalZs3Symbol::SIX,
+            AdditionalSignalZs3Symbol::EIGHT,
+        ],
+        AdditionalSignalZs3Symbol::OFF,
+        "A-Zs3".to_string(),
+    );
+    let zs3v = AdditionalSignalZs3::new_arc(
+        true,
+        vec![AdditionalSignalZs3Symbol::SIX],
+        AdditionalSignalZs3Symbol::OFF,
+        "A-Zs3v".to_string(),
+    );
+    a.write().unwrap().mount_zs3(zs3.clone());
+    a.write().unwrap().mount_zs3(zs3v.clone());
+    assert!(a.read().unwrap().zs3v_indicator().is_some());
+    assert!(!a.read().unwrap().supports(SignalState::new(
+        MainSignalState::Ks1,
+        AdditionalSignalState::Off,
+        AdditionalSignalZs3Symbol::TEN,
+        AdditionalSignalZs3Symbol::OFF,
+    )));
+
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(a.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        a.clone(),
+        b.clone(),
+    );
+    dw.set_speed(AdditionalSignalZs3Symbol::SIX);
+    dw.set_way().unwrap();
+    assert_eq!(zs3.read().unwrap().state(), AdditionalSignalZs3Symbol::SIX);
+    let state = a.read().unwrap().state();
+    assert_eq!(state.main(), MainSignalState::Ks2);
+    assert_eq!(state.zs3(), AdditionalSignalZs3Symbol::SIX);
+    assert!(dw.supervise().unwrap().is_empty());
+
+    zs3.write()
+        .unwrap()
+        .set_state(AdditionalSignalZs3Symbol::EIGHT)
+        .unwrap();
+    assert_eq!(
+        dw.supervise().unwrap(),
+        vec![DrivewayDeviation::SignalState(
+            "A".to_string(),
+            SignalState::new(
+                MainSignalState::Ks2,
+                AdditionalSignalState::Off,
+                AdditionalSignalZs3Symbol::EIGHT,
+                AdditionalSignalZs3Symbol::OFF,
+            )
+        )]
+    );
+    assert_eq!(zs3.read().unwrap().state(), AdditionalSignalZs3Symbol::OFF);
+}
+
+#[test]
+fn route_speed() {
+    assert_eq!(
+        AdditionalSignalZs3Symbol::from_speed(65),
+        Some(AdditionalSignalZs3Symbol::SIX)
+    );
+    assert_eq!(
+        AdditionalSignalZs3Symbol::from_speed(200),
+        Some(AdditionalSignalZs3Symbol::SIXTEEN)
+    );
+    assert_eq!(AdditionalSignalZs3Symbol::from_speed(5), None);
+
+    let zs3_signal = |id: &str| {
+        Signal::new_arc(
+            (MainSignalState::Hp0).into(),
+            SupportedSignalStates::default()
+                .main(&mut vec![
+                    MainSignalState::Hp0,
+                    MainSignalState::Ks1,
+                    MainSignalState::Ks1Flashing,
+                    MainSignalState::Ks2,
+                ])
+                .zs3(&mut vec![AdditionalSignalZs3Symbol::SIX])
+                .zs3v(&mut vec![AdditionalSignalZs3Symbol::SIX]),
+            id.to_string(),
+            None,
+        )
+    };
+    let x = zs3_signal("X");
+    let a = zs3_signal("A");
+    let b = zs3_signal("B");
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    p1.write()
+        .unwrap()
+        .set_diverging_speed(PointState::Right, 60);
+    let dw1 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            Vec::new(),
+            vec![(x.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        x.clone(),
+        a.clone(),
+    )));
+    let dw2 = Arc::new(RwLock::new(Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Right)],
+            vec![(a.clone(), (MainSignalState::Ks1).into())],
+            Vec::new(),
+        ),
+        a.clone(),
+        b.clone(),
+    )));
+    dw1.write().unwrap().set_line_speed(100);
+    dw2.write().unwrap().set_line_speed(100);
+    assert_eq!(dw1.read().unwrap().permitted_speed(), Some(100));
+    assert_eq!(dw2.read().unwrap().permitted_speed(), Some(60));
+    let mut manager = DrivewayManager::new(BTreeMap::new());
+    manager.add(dw1.clone());
+    manager.add(dw2.clone());
+
+    manager.set_driveway("X", "A").unwrap();
+    let x_state = x.read().unwrap().state();
+    assert_eq!(x_state.main(), MainSignalState::Ks2);
+    assert_eq!(x_state.zs3(), AdditionalSignalZs3Symbol::OFF);
+
+    manager.set_driveway("A", "B").unwrap();
+    let a_state = a.read().unwrap().state();
+    assert_eq!(a_state.main(), MainSignalState::Ks2);
+    assert_eq!(a_state.zs3(), AdditionalSignalZs3Symbol::SIX);
+    let x_state = x.read().unwrap().state();
+    assert_eq!(x_state.main(), MainSignalState::Ks1Flashing);
+    assert_eq!(x_state.zs3v(), AdditionalSignalZs3Symbol::SIX);
+
+    manager.release_driveway("A", "B").unwrap();
+    p1.write()
+        .unwrap()
+        .set_diverging_speed(PointState::Right, 5);
+    assert!(matches!(
+        manager.set_driveway("A", "B"),
+        Err(TrackElementError::SpeedNotSignallable(5))
+    ));
+    assert_eq!(a.read().unwrap().state().main(), MainSignalState::Hp0);
+    assert_eq!(dw2.read().unwrap().status(), DrivewayStatus::Idle);
+}
+
+#[test]
+fn signalled_speed_is_capped_by_points() {
+    let a = Signal::new_arc(
+        (MainSignalState::Hp0).into(),
+        SupportedSignalStates::default()
+            .main(&mut vec![
+                MainSignalState::Hp0,
+                MainSignalState::Ks1,
+                MainSignalState::Ks2,
+            ])
+            .zs3(&mut vec![
+                AdditionalSignalZs3Symbol::FOUR,
+                AdditionalSignalZs3Symbol::SIX,
+                AdditionalSignalZs3Symbol::SIXTEEN,
+            ]),
+        "A".to_string(),
+        None,
+    );
+    let b = ks_signal("B");
+    let p1 = Point::new_arc(PointState::Left, "P1".to_string());
+    p1.write()
+        .unwrap()
+        .set_diverging_speed(PointState::Right, 40);
+    let mut dw = Driveway::new(
+        Vec::new(),
+        DrivewayState::new(
+            vec![(p1.clone(), PointState::Right)],
+            vec![(
+                a.clone(),
+                SignalState::new(
+                    MainSignalState::Ks1,
+                    AdditionalSignalState::Off,
+                    AdditionalSignalZs3Symbol::SIXTEEN,
+                    AdditionalSignalZs3Symbol::OFF,
+                ),
+            )],
+            Vec::new(),
+        ),
+        a.clone(),
+        b.clone(),
+    );
+    dw.set_line_speed(160);
+
+    dw.set_way().unwrap();
+    assert_eq!(
+        a.read().unwrap().state().zs3(),
+        AdditionalSignalZs3Symbol::FOUR
+    );
+    dw.cancel().unwrap();
+
+    dw.set_speed(AdditionalSignalZs3Symbol::SIXTEEN);
+    dw.set_way().unwrap();
+    assert_eq!(
+        a.read().unwrap().state().zs3(),
+        AdditionalSignalZs3Symbol::FOUR
+    );
+    dw.cancel().unwrap();
+
+    // A manual speed below the points' limit is shown as requested
+    p1.write()
+        .unwrap()
+        .set_diverging_speed(PointState::Right, 80);
+    dw.set_speed(AdditionalSignalZs3Symbol::SIX);
+    dw.set_way().unwrap();
+    assert_eq!(
+        a.read().unwrap().state().zs3(),
+        AdditionalSignalZs3Symbol::SIX
+    );
+}
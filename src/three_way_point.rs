@@ -0,0 +1,77 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+
+use crate::point::{Lockable, PointLock};
+use crate::{TrackElement, TrackElementError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThreeWayPointState {
+    Left,
+    #[default]
+    Straight,
+    Right,
+}
+
+#[derive(Debug)]
+pub struct ThreeWayPoint {
+    state: ThreeWayPointState,
+    reported: Option<ThreeWayPointState>,
+    id: String,
+    locks: BTreeSet<PointLock>,
+}
+
+impl ThreeWayPoint {
+    pub fn new(state: ThreeWayPointState, id: String) -> Self {
+        Self {
+            state,
+            reported: None,
+            id,
+            locks: BTreeSet::new(),
+        }
+    }
+
+    pub fn new_arc(state: ThreeWayPointState, id: String) -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::new(state, id)))
+    }
+
+    pub fn report_state(&mut self, state: ThreeWayPointState) {
+        self.reported = Some(state);
+        println!("Three-way point {} reports {:?}", self.id, state);
+    }
+}
+
+impl Lockable for ThreeWayPoint {
+    fn locks(&self) -> &BTreeSet<PointLock> {
+        &self.locks
+    }
+
+    fn locks_mut(&mut self) -> &mut BTreeSet<PointLock> {
+        &mut self.locks
+    }
+}
+
+impl TrackElement for ThreeWayPoint {
+    type State = ThreeWayPointState;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn state(&self) -> Self::State {
+        self.reported.unwrap_or(self.state)
+    }
+
+    fn commanded_state(&self) -> Self::State {
+        self.state
+    }
+
+    fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
+        if self.is_locked() && new_state != self.state {
+            return Err(TrackElementError::PointLocked(self.id.clone()));
+        }
+        self.state = new_state;
+        self.reported = None;
+        println!("Three-way point {} is now {:?}", self.id, self.state);
+        Ok(())
+    }
+}
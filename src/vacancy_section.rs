@@ -1,49 +1,86 @@
 use std::sync::{Arc, RwLock};
 
-use crate::{
-    signal::{MainSignalState, Signal},
-    TrackElement,
-};
+use crate::backend::{Backend, SimulationBackend};
+use crate::{TrackElement, TrackElementError};
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum VacancySectionState {
     #[default]
     Free,
     Occupied,
     CommunicationError,
-    Disturbed
+    Disturbed,
+}
+
+impl VacancySectionState {
+    pub fn is_possibly_occupied(&self) -> bool {
+        !matches!(self, VacancySectionState::Free)
+    }
+
+    pub fn requires_reset(&self) -> bool {
+        matches!(
+            self,
+            VacancySectionState::CommunicationError | VacancySectionState::Disturbed
+        )
+    }
 }
 
 #[derive(Debug)]
 pub struct VacancySection {
     id: String,
     state: VacancySectionState,
-    previous_signals: Vec<Arc<RwLock<Signal>>>,
+    reported: Option<VacancySectionState>,
+    reset_required: bool,
+    resets: Vec<(String, VacancySectionState)>,
+    backend: Arc<dyn Backend<VacancySectionState>>,
 }
 
 impl VacancySection {
-    pub fn new(
-        id: String,
-        state: VacancySectionState,
-        previous_signals: Vec<Arc<RwLock<Signal>>>,
-    ) -> Self {
+    pub fn new(id: String, state: VacancySectionState) -> Self {
         Self {
             id,
             state,
-            previous_signals,
+            reported: None,
+            reset_required: state.requires_reset(),
+            resets: Vec::new(),
+            backend: SimulationBackend::new_arc(),
         }
     }
 
-    pub fn new_arc(
-        id: String,
-        state: VacancySectionState,
-        previous_signals: Vec<Arc<RwLock<Signal>>>,
-    ) -> Arc<RwLock<Self>> {
-        Arc::new(RwLock::new(Self::new(id, state, previous_signals)))
+    pub fn new_arc(id: String, state: VacancySectionState) -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::new(id, state)))
+    }
+
+    pub fn set_backend(&mut self, backend: Arc<dyn Backend<VacancySectionState>>) {
+        self.backend = backend;
+    }
+
+    pub fn reset(&mut self, operator: &str) -> Result<(), TrackElementError> {
+        let state = self.state();
+        println!(
+            "Vacancy section {} reset from {:?} to Free by {operator}",
+            self.id, state
+        );
+        self.backend.command(&self.id, VacancySectionState::Free)?;
+        self.resets.push((operator.to_string(), state));
+        self.state = VacancySectionState::Free;
+        self.reported = None;
+        self.reset_required = false;
+        Ok(())
+    }
+
+    pub fn report_state(&mut self, state: VacancySectionState) {
+        self.reported = Some(state);
+        self.reset_required |= state.requires_reset();
+        println!("Vacancy section {} reports {:?}", self.id, state);
+    }
+
+    pub fn reset_required(&self) -> bool {
+        self.reset_required
     }
 
-    pub fn previous_signals(&self) -> &[Arc<RwLock<Signal>>] {
-        &self.previous_signals
+    pub fn resets(&self) -> &[(String, VacancySectionState)] {
+        &self.resets
     }
 }
 
@@ -55,19 +92,28 @@ impl TrackElement for VacancySection {
     }
 
     fn state(&self) -> Self::State {
+        self.reported
+            .or_else(|| self.backend.status(&self.id))
+            .unwrap_or(self.state)
+    }
+
+    fn commanded_state(&self) -> Self::State {
         self.state
     }
 
-    fn set_state(&mut self, new_state: Self::State) -> Result<(), crate::TrackElementError> {
+    fn set_state(&mut self, new_state: Self::State) -> Result<(), TrackElementError> {
         // TODO: Better logic, probably more like "wait until state equals expected state"
-        self.state = new_state;
-        for signal in &self.previous_signals {
-            let mut signal = signal.write().unwrap();
-            match new_state {
-                VacancySectionState::Occupied => signal.set_state(MainSignalState::Hp0.into())?,
-                _ => (),
-            }
+        // Once disturbed, the section stays latched until an operator resets it
+        self.reset_required |= self.state().requires_reset();
+        if self.reset_required && !new_state.is_possibly_occupied() {
+            return Err(TrackElementError::VacancySectionResetRequired(
+                self.id.clone(),
+            ));
         }
+        self.backend.command(&self.id, new_state)?;
+        self.state = new_state;
+        self.reported = None;
+        self.reset_required |= new_state.requires_reset();
         Ok(())
     }
 }